@@ -0,0 +1,334 @@
+use crate::SudokuError;
+use std::fmt;
+
+// index: 0..81 => row index / 9, column index % 9
+pub const ALL: u16 = 0b1_1111_1111;
+
+const fn block_of(index: usize) -> usize {
+    (index / 27) * 3 + (index % 9) / 3
+}
+
+// the cells of the 9 rows, 9 columns and 9 3x3 blocks
+const HOUSES: [[u8; 9]; 27] = houses();
+
+const fn houses() -> [[u8; 9]; 27] {
+    let mut houses = [[0; 9]; 27];
+    let mut i = 0;
+    while i < 9 {
+        let mut j = 0;
+        while j < 9 {
+            houses[i][j] = (i * 9 + j) as u8;
+            houses[9 + i][j] = (j * 9 + i) as u8;
+            houses[18 + i][j] = ((i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3) as u8;
+            j += 1;
+        }
+        i += 1;
+    }
+    houses
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitboard {
+    cells: [u16; 81],          // candidates
+    values: [u8; 81],          // 0 = empty
+    rows: [u16; 9],            // digits used per row
+    columns: [u16; 9],         // digits used per column
+    blocks: [u16; 9],          // digits used per block
+    block_of: [u8; 81],        // block of each cell, 3x3 unless jigsaw
+    block_cells: [[u8; 9]; 9], // the cells of each block
+}
+impl Default for Bitboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Display for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for v in &self.values {
+            write!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+impl Bitboard {
+    pub fn new() -> Bitboard {
+        Bitboard {
+            cells: [ALL; 81],
+            values: [0; 81],
+            rows: [0; 9],
+            columns: [0; 9],
+            blocks: [0; 9],
+            block_of: std::array::from_fn(|index| block_of(index) as u8),
+            block_cells: std::array::from_fn(|b| HOUSES[18 + b]),
+        }
+    }
+    // jigsaw blocks, the block number 1~9 of each cell as in set_jigsaw; the
+    // layout is taken as valid, each block holding 9 cells
+    pub(crate) fn with_blocks(blocks: &[usize]) -> Bitboard {
+        let mut board = Bitboard::new();
+        let mut filled = [0; 9];
+        for (index, &b) in blocks.iter().enumerate() {
+            board.block_of[index] = (b - 1) as u8;
+            board.block_cells[b - 1][filled[b - 1]] = index as u8;
+            filled[b - 1] += 1;
+        }
+        board
+    }
+    // 81 chars, '1'~'9' are givens, '0' or '.' are blanks, whitespace is skipped.
+    pub fn parse(str: &str) -> Result<Bitboard, SudokuError> {
+        let chars: Vec<char> = str.chars().filter(|ch| !ch.is_whitespace()).collect();
+        if chars.len() != 81 {
+            return Err(SudokuError::InvalidLength(chars.len(), 81));
+        }
+        let mut board = Bitboard::new();
+        for (index, &ch) in chars.iter().enumerate() {
+            match ch {
+                '1'..='9' => {
+                    let value = ch as u8 - b'0';
+                    if !board.place(index, value) {
+                        return Err(board.conflict(index, value));
+                    }
+                }
+                '0' | '.' => (),
+                _ => return Err(SudokuError::InvalidDigit(index + 1, ch)),
+            }
+        }
+        Ok(board)
+    }
+    pub fn value(&self, index: usize) -> u8 {
+        self.values[index]
+    }
+    pub fn candidates(&self, index: usize) -> u16 {
+        if self.values[index] != 0 {
+            return 0;
+        }
+        let used = self.rows[index / 9]
+            | self.columns[index % 9]
+            | self.blocks[self.block_of[index] as usize];
+        self.cells[index] & !used
+    }
+    pub fn place(&mut self, index: usize, value: u8) -> bool {
+        if !(1..=9).contains(&value) || self.candidates(index) & (1 << (value - 1)) == 0 {
+            return false;
+        }
+        self.assign(index, value);
+        true
+    }
+    pub fn remove(&mut self, index: usize, value: u8) {
+        if (1..=9).contains(&value) {
+            self.cells[index] &= !(1 << (value - 1));
+        }
+    }
+    pub fn restrict(&mut self, index: usize, mask: u16) {
+        self.cells[index] &= mask;
+    }
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(|&v| v != 0)
+    }
+    pub fn solve(&mut self) -> bool {
+        let mut board = *self;
+        let mut count = 0;
+        let mut solution = *self;
+        board.search(1, &mut count, &mut solution);
+        if count > 0 {
+            *self = solution;
+        }
+        count > 0
+    }
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        let mut board = *self;
+        let mut count = 0;
+        let mut solution = *self;
+        board.search(limit, &mut count, &mut solution);
+        count
+    }
+    fn conflict(&self, index: usize, value: u8) -> SudokuError {
+        let pos = (index / 9 + 1, index % 9 + 1);
+        let mut with = pos;
+        for i in 0..81 {
+            if i != index
+                && self.values[i] == value
+                && (i / 9 == index / 9
+                    || i % 9 == index % 9
+                    || self.block_of[i] == self.block_of[index])
+            {
+                with = (i / 9 + 1, i % 9 + 1);
+            }
+        }
+        SudokuError::Conflict { pos, value, with }
+    }
+    fn assign(&mut self, index: usize, value: u8) {
+        let bit = 1 << (value - 1);
+        self.values[index] = value;
+        self.rows[index / 9] |= bit;
+        self.columns[index % 9] |= bit;
+        self.blocks[self.block_of[index] as usize] |= bit;
+    }
+    // place naked and hidden singles until there are none left; false when a
+    // cell has no candidate or a digit has no place in a house
+    fn propagate(&mut self) -> bool {
+        loop {
+            let mut progress = false;
+            for index in 0..81 {
+                if self.values[index] != 0 {
+                    continue;
+                }
+                let mask = self.candidates(index);
+                match mask.count_ones() {
+                    0 => return false,
+                    1 => {
+                        self.assign(index, mask.trailing_zeros() as u8 + 1);
+                        progress = true;
+                    }
+                    _ => (),
+                }
+            }
+            let blocks = self.block_cells;
+            for house in HOUSES[..18].iter().chain(&blocks) {
+                let (mut once, mut twice, mut placed) = (0, 0, 0);
+                for &index in house {
+                    match self.values[index as usize] {
+                        0 => {
+                            let mask = self.candidates(index as usize);
+                            twice |= once & mask;
+                            once |= mask;
+                        }
+                        v => placed |= 1 << (v - 1),
+                    }
+                }
+                if once | placed != ALL {
+                    return false;
+                }
+                let mut hidden = once & !twice;
+                while hidden != 0 {
+                    let bit = hidden & hidden.wrapping_neg();
+                    hidden ^= bit;
+                    let index = house
+                        .iter()
+                        .map(|&index| index as usize)
+                        .find(|&index| self.candidates(index) & bit != 0);
+                    match index {
+                        Some(index) => self.assign(index, bit.trailing_zeros() as u8 + 1),
+                        None => return false,
+                    }
+                    progress = true;
+                }
+            }
+            if !progress {
+                return true;
+            }
+        }
+    }
+    // singles first, then a guess on the empty cell with the fewest
+    // candidates; every guess works on a copy of the board
+    fn search(&mut self, limit: usize, count: &mut usize, solution: &mut Bitboard) {
+        if !self.propagate() {
+            return;
+        }
+        let mut best = 81;
+        let mut best_count = 10;
+        for i in 0..81 {
+            if self.values[i] == 0 {
+                let n = self.candidates(i).count_ones();
+                if n < best_count {
+                    best = i;
+                    best_count = n;
+                    if n <= 1 {
+                        break;
+                    }
+                }
+            }
+        }
+        if best == 81 {
+            *count += 1;
+            if *count == 1 {
+                *solution = *self;
+            }
+            return;
+        }
+        let mut mask = self.candidates(best);
+        while mask != 0 {
+            let bit = mask & mask.wrapping_neg();
+            mask ^= bit;
+            let mut board = *self;
+            board.assign(best, bit.trailing_zeros() as u8 + 1);
+            board.search(limit, count, solution);
+            if *count >= limit {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bitboard, ALL, HOUSES};
+    use crate::SudokuError;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    // every row, column and block holds each digit once
+    fn valid(board: &Bitboard) -> bool {
+        board.is_solved()
+            && HOUSES.iter().all(|house| {
+                let used = house
+                    .iter()
+                    .fold(0, |m, &i| m | 1 << (board.value(i as usize) - 1));
+                used == ALL
+            })
+    }
+
+    #[test]
+    fn parse_rejects_bad_input() {
+        assert_eq!(
+            Bitboard::parse("53").err(),
+            Some(SudokuError::InvalidLength(2, 81))
+        );
+        let conflict = PUZZLE.replacen('3', "5", 1);
+        assert!(matches!(
+            Bitboard::parse(&conflict),
+            Err(SudokuError::Conflict {
+                pos: (1, 2),
+                value: 5,
+                with: (1, 1)
+            })
+        ));
+    }
+
+    #[test]
+    fn solves_unique_puzzle() {
+        let mut board = Bitboard::parse(PUZZLE).unwrap();
+        assert_eq!(board.count_solutions(2), 1);
+        assert!(board.solve());
+        assert_eq!(board.to_string(), SOLUTION);
+    }
+
+    #[test]
+    fn empty_grid_has_many_solutions() {
+        let mut board = Bitboard::new();
+        assert_eq!(board.count_solutions(2), 2);
+        assert!(board.solve());
+        assert!(valid(&board));
+    }
+
+    #[test]
+    fn jigsaw_blocks_are_used() {
+        let blocks: Vec<usize> = "111122333111222333112222333\
+                                  444555566444555666444556666\
+                                  777788999777888999778888999"
+            .bytes()
+            .map(|b| (b - b'0') as usize)
+            .collect();
+        let mut board = Bitboard::with_blocks(&blocks);
+        assert!(board.solve());
+        for b in 0..9 {
+            let used = board.block_cells[b]
+                .iter()
+                .fold(0u16, |m, &i| m | 1 << (board.value(i as usize) - 1));
+            assert_eq!(used, ALL);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{value_char, Note, Sudoku, SudokuError};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeKind {
+    White, // Kropki white dot: consecutive digits
+    Black, // Kropki black dot: one digit is double the other
+    X,     // the two digits add up to 10
+    V,     // the two digits add up to 5
+    Less,  // inequality sign, the first cell holds the smaller digit
+}
+impl EdgeKind {
+    // whether x in the first cell and y in the second fit the marking
+    pub fn allows(self, x: usize, y: usize) -> bool {
+        match self {
+            EdgeKind::White => x + 1 == y || y + 1 == x,
+            EdgeKind::Black => x == 2 * y || y == 2 * x,
+            EdgeKind::X => x + y == 10,
+            EdgeKind::V => x + y == 5,
+            EdgeKind::Less => x < y,
+        }
+    }
+}
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EdgeKind::White => write!(f, "白点"),
+            EdgeKind::Black => write!(f, "黑点"),
+            EdgeKind::X => write!(f, "X"),
+            EdgeKind::V => write!(f, "V"),
+            EdgeKind::Less => write!(f, "小于号"),
+        }
+    }
+}
+
+// a marking between two orthogonally adjacent cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edge {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+    pub kind: EdgeKind,
+}
+
+impl Sudoku {
+    pub fn add_edge(
+        &mut self,
+        a: (usize, usize),
+        b: (usize, usize),
+        kind: EdgeKind,
+    ) -> Result<usize, SudokuError> {
+        self.try_get_cell(a)?;
+        self.try_get_cell(b)?;
+        if !self.neighbours(a).contains(&b) || self.edge_between(a, b).is_some() {
+            return Err(SudokuError::InvalidEdge(a, b));
+        }
+        self.edges.push(Edge { a, b, kind });
+        Ok(self.edges.len())
+    }
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+    pub fn clear_edges(&mut self) {
+        self.edges.clear();
+    }
+    pub fn edge_between(&self, a: (usize, usize), b: (usize, usize)) -> Option<&Edge> {
+        self.edges
+            .iter()
+            .find(|e| (e.a, e.b) == (a, b) || (e.a, e.b) == (b, a))
+    }
+    // negative Kropki: every consecutive or 1:2 pair is marked with its dot
+    pub fn set_kropki_negative(&mut self, kropki_negative: bool) {
+        self.kropki_negative = kropki_negative;
+    }
+    pub fn is_kropki_negative(&self) -> bool {
+        self.kropki_negative
+    }
+    // negative XV: every pair adding up to 10 or 5 is marked with X or V
+    pub fn set_xv_negative(&mut self, xv_negative: bool) {
+        self.xv_negative = xv_negative;
+    }
+    pub fn is_xv_negative(&self) -> bool {
+        self.xv_negative
+    }
+    pub(crate) fn has_pair_rules(&self) -> bool {
+        !self.edges.is_empty() || self.has_unmarked_rules()
+    }
+    fn has_unmarked_rules(&self) -> bool {
+        self.rules.non_consecutive || self.kropki_negative || self.xv_negative
+    }
+    // the rule between two neighbours without a marking
+    fn unmarked_allows(&self, x: usize, y: usize) -> bool {
+        if (self.rules.non_consecutive || self.kropki_negative) && EdgeKind::White.allows(x, y) {
+            return false;
+        }
+        if self.xv_negative && (EdgeKind::X.allows(x, y) || EdgeKind::V.allows(x, y)) {
+            return false;
+        }
+        !(self.kropki_negative && EdgeKind::Black.allows(x, y))
+    }
+    fn pair_allows(&self, a: (usize, usize), b: (usize, usize), x: usize, y: usize) -> bool {
+        match self.edge_between(a, b) {
+            Some(e) if e.a == a => e.kind.allows(x, y),
+            Some(e) => e.kind.allows(y, x),
+            None => self.unmarked_allows(x, y),
+        }
+    }
+    // the neighbouring pairs that carry a rule
+    fn constrained_pairs(&self) -> Vec<((usize, usize), (usize, usize))> {
+        if !self.has_unmarked_rules() {
+            return self.edges.iter().map(|e| (e.a, e.b)).collect();
+        }
+        let mut pairs = Vec::new();
+        for r in 1..=self.size {
+            for c in 1..=self.size {
+                for b in self.neighbours((r, c)) {
+                    if (r, c) < b {
+                        pairs.push(((r, c), b));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+    // drop candidates that have no partner across a marked or constrained edge
+    pub fn check_edges(&mut self) {
+        if !self.has_pair_rules() {
+            return;
+        }
+        for (a, b) in self.constrained_pairs() {
+            for (p, q) in [(a, b), (b, a)] {
+                let other = self.data[[q.0 - 1, q.1 - 1]].get_values();
+                let mut clear = Vec::new();
+                for x in self.data[[p.0 - 1, p.1 - 1]].get_values() {
+                    if !other.iter().any(|&y| self.pair_allows(p, q, x, y)) {
+                        clear.push(x);
+                    }
+                }
+                let cell = &mut self.data[[p.0 - 1, p.1 - 1]];
+                for x in clear {
+                    cell.log.push(Note::Text(format!(
+                        "与 {}:{} 的相邻规则不允许值{}",
+                        q.0,
+                        q.1,
+                        value_char(x as u8)
+                    )));
+                    cell.clear_bit(x - 1);
+                }
+            }
+        }
+    }
+    // every filled neighbouring pair keeps its rule
+    pub(crate) fn edges_hold(&self) -> bool {
+        self.constrained_pairs().into_iter().all(|(a, b)| {
+            let x = self.data[[a.0 - 1, a.1 - 1]].value as usize;
+            let y = self.data[[b.0 - 1, b.1 - 1]].value as usize;
+            x == 0 || y == 0 || self.pair_allows(a, b, x, y)
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use std::{error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SudokuError {
+    InvalidPosition(usize, usize),
+    InvalidIndex(usize),
+    InvalidValue(u8),
+    InvalidDigit(usize, char),   // 1-based position in the input
+    InvalidLength(usize, usize), // found, expected
+    InvalidSize(usize, usize),   // box rows, box columns
+    InvalidRegion(usize),        // cell count
+    InvalidJigsaw(char),         // region label in the map
+    InvalidCage(usize),          // cage sum
+    InvalidEdge((usize, usize), (usize, usize)),
+    InvalidPath((usize, usize)), // first cell that breaks the path
+    InvalidSandwich(usize),      // sandwich sum
+    InvalidClue(usize),          // outside clue value
+    InvalidLine(usize),          // line number in a puzzle text
+    EmptyLayout,                 // multi-grid layout without grids
+    InvalidOverlap((usize, usize), (usize, usize)), // corners of two grids
+    InvalidImage,
+    Unsupported,
+    CellFilled((usize, usize), u8),
+    GivenCell((usize, usize), u8), // a puzzle clue, not to be erased
+    ParityViolation((usize, usize), u8), // digit of the wrong parity
+    Conflict {
+        pos: (usize, usize),
+        value: u8,
+        with: (usize, usize),
+    },
+}
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SudokuError::InvalidPosition(r, c) => write!(f, "位置 {}:{} 超出范围", r, c),
+            SudokuError::InvalidIndex(i) => write!(f, "编号 {} 超出范围", i),
+            SudokuError::InvalidValue(v) => write!(f, "值 {} 不是有效数字", v),
+            SudokuError::InvalidDigit(i, ch) => write!(f, "第 {} 个字符 '{}' 无效", i, ch),
+            SudokuError::InvalidLength(n, m) => write!(f, "输入长度为 {}, 应为 {}", n, m),
+            SudokuError::InvalidSize(r, c) => write!(f, "不支持 {}x{} 的宫格", r, c),
+            SudokuError::InvalidRegion(n) => {
+                write!(f, "区域包含 {} 个格子, 格子数应与数字个数相同且不重复", n)
+            }
+            SudokuError::InvalidJigsaw(ch) => {
+                write!(f, "不规则区域 '{}' 的格子数不对或者不连通", ch)
+            }
+            SudokuError::InvalidCage(sum) => {
+                write!(f, "和为 {} 的笼子无效或者与其他笼子重叠", sum)
+            }
+            SudokuError::InvalidEdge(a, b) => {
+                write!(f, "{}:{} 与 {}:{} 不相邻或者已有标记", a.0, a.1, b.0, b.1)
+            }
+            SudokuError::InvalidPath(pos) => {
+                write!(f, "路径在 {}:{} 处重复、断开或者长度不对", pos.0, pos.1)
+            }
+            SudokuError::InvalidSandwich(sum) => write!(f, "三明治的和 {} 无效", sum),
+            SudokuError::InvalidClue(v) => write!(f, "外部提示 {} 无效", v),
+            SudokuError::InvalidLine(n) => write!(f, "第 {} 行格式错误", n),
+            SudokuError::EmptyLayout => write!(f, "布局中没有宫格"),
+            SudokuError::InvalidOverlap(a, b) => write!(
+                f,
+                "左上角为 {}:{} 与 {}:{} 的宫格重叠部分不是完整的 3x3 块",
+                a.0, a.1, b.0, b.1
+            ),
+            SudokuError::InvalidImage => write!(f, "图片无法读取或者找不到 9x9 的盘面"),
+            SudokuError::Unsupported => write!(
+                f,
+                "位棋盘只支持只有行、列、块的 9x9 数独（块可以是不规则区域）"
+            ),
+            SudokuError::CellFilled(pos, v) => {
+                write!(f, "{}:{} 已经填入值 {}", pos.0, pos.1, v)
+            }
+            SudokuError::GivenCell(pos, v) => {
+                write!(f, "{}:{} 的值 {} 是题目给出的, 不能擦除", pos.0, pos.1, v)
+            }
+            SudokuError::ParityViolation(pos, v) => {
+                write!(f, "{}:{} 的奇偶限制不允许值 {}", pos.0, pos.1, v)
+            }
+            SudokuError::Conflict { pos, value, with } => write!(
+                f,
+                "{}:{} 的值 {} 与 {}:{} 冲突",
+                pos.0, pos.1, value, with.0, with.1
+            ),
+        }
+    }
+}
+impl error::Error for SudokuError {}
@@ -0,0 +1,173 @@
+use crate::{value_char, Sudoku, SudokuError};
+
+// Puzzle files of other tools, all for the standard 9x9 grid:
+//
+//   Sdk         SadMan Sudoku, 9 rows of '.' and digits, '#' metadata lines
+//   Sdm         SadMan collection, one 81-digit puzzle per line, '0' blank
+//   Ss          Simple Sudoku, rows with '|' between blocks and '-' lines
+//   OpenSudoku  XML, one <game data="..."/> per puzzle
+//   Dot         one 81-character line per puzzle, '.' blank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Sdk,
+    Sdm,
+    Ss,
+    OpenSudoku,
+    Dot,
+}
+
+impl Format {
+    // by file extension, e.g. "sdk" or "puzzles.opensudoku"
+    pub fn from_extension(name: &str) -> Option<Format> {
+        let ext = name.rsplit('.').next()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "sdk" => Some(Format::Sdk),
+            "sdm" => Some(Format::Sdm),
+            "ss" => Some(Format::Ss),
+            "opensudoku" | "xml" => Some(Format::OpenSudoku),
+            "txt" => Some(Format::Dot),
+            _ => None,
+        }
+    }
+    pub fn read(self, text: &str) -> Result<Vec<Sudoku>, SudokuError> {
+        match self {
+            Format::Sdk => read_sdk(text).map(|s| vec![s]),
+            Format::Ss => read_ss(text).map(|s| vec![s]),
+            Format::Sdm | Format::Dot => read_lines(text),
+            Format::OpenSudoku => read_opensudoku(text),
+        }
+    }
+    // Sdk and Ss hold a single puzzle, only the first one is written; every
+    // puzzle must be 9x9
+    pub fn write(self, puzzles: &[Sudoku]) -> Result<String, SudokuError> {
+        if let Some(s) = puzzles.iter().find(|s| s.size() != 9) {
+            let (rows, columns) = s.box_shape();
+            return Err(SudokuError::InvalidSize(rows, columns));
+        }
+        let mut text = String::new();
+        match self {
+            Format::Sdk => {
+                if let Some(s) = puzzles.first() {
+                    for r in 1..=9 {
+                        text += &row_text(s, r, '.');
+                        text.push('\n');
+                    }
+                }
+            }
+            Format::Ss => {
+                if let Some(s) = puzzles.first() {
+                    for r in 1..=9 {
+                        let row = row_text(s, r, '.');
+                        text += &format!("{}|{}|{}\n", &row[..3], &row[3..6], &row[6..]);
+                        if r == 3 || r == 6 {
+                            text += "-----------\n";
+                        }
+                    }
+                }
+            }
+            Format::Sdm | Format::Dot => {
+                let blank = match self {
+                    Format::Sdm => '0',
+                    _ => '.',
+                };
+                for s in puzzles {
+                    for r in 1..=9 {
+                        text += &row_text(s, r, blank);
+                    }
+                    text.push('\n');
+                }
+            }
+            Format::OpenSudoku => {
+                text += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+                text += "<opensudoku>\n";
+                for s in puzzles {
+                    let data: String = (1..=9).map(|r| row_text(s, r, '0')).collect();
+                    text += &format!("  <game data=\"{}\" />\n", data);
+                }
+                text += "</opensudoku>\n";
+            }
+        }
+        Ok(text)
+    }
+}
+
+fn row_text(sudoku: &Sudoku, row: usize, blank: char) -> String {
+    sudoku
+        .get_row(row)
+        .iter()
+        .map(|c| match c.value {
+            0 => blank,
+            v => value_char(v),
+        })
+        .collect()
+}
+
+fn puzzle(givens: &str) -> Result<Sudoku, SudokuError> {
+    let mut sudoku = Sudoku::new();
+    sudoku.try_init(givens)?;
+    Ok(sudoku)
+}
+
+// '#' lines are metadata; a "[Puzzle]" header may come first and any other
+// section, such as the solving state, ends the grid
+fn read_sdk(text: &str) -> Result<Sudoku, SudokuError> {
+    let mut givens = String::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() || line == "[Puzzle]" {
+            continue;
+        }
+        if line.starts_with('[') {
+            break;
+        }
+        givens += line;
+    }
+    puzzle(&givens)
+}
+
+fn read_ss(text: &str) -> Result<Sudoku, SudokuError> {
+    let mut givens = String::new();
+    for line in text.lines().map(str::trim) {
+        if line.chars().all(|ch| matches!(ch, '-' | '+' | '*')) {
+            continue;
+        }
+        givens.extend(line.chars().filter(|&ch| ch != '|' && ch != '!'));
+    }
+    puzzle(&givens)
+}
+
+fn read_lines(text: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    let mut result = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // some collections add a rating or name after the digits
+        let givens = line.split_whitespace().next().unwrap_or("");
+        result.push(puzzle(givens).map_err(|e| match e {
+            SudokuError::InvalidLength(..) => SudokuError::InvalidLine(i + 1),
+            e => e,
+        })?);
+    }
+    Ok(result)
+}
+
+fn read_opensudoku(text: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    let mut result = Vec::new();
+    for (start, _) in text.match_indices("<game") {
+        let tag = &text[start..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let data = tag
+            .split_once("data=\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(data, _)| data);
+        match data {
+            Some(data) => result.push(puzzle(data)?),
+            None => {
+                let line = text[..start].matches('\n').count() + 1;
+                return Err(SudokuError::InvalidLine(line));
+            }
+        }
+    }
+    Ok(result)
+}
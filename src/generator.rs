@@ -0,0 +1,186 @@
+use crate::{Edge, EdgeKind, Sudoku};
+use ndarray_rand::rand::{seq::SliceRandom, thread_rng, Rng};
+
+// search steps for checking one removed given of a variant without a Bitboard
+const REMOVAL_STEPS: usize = 200;
+
+impl Sudoku {
+    // counting stops once limit solutions are found
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        // usize::MAX steps are never used up
+        self.count_solutions_within(limit, usize::MAX).unwrap_or(0)
+    }
+    // like count_solutions, but None once budget search steps are used up
+    fn count_solutions_within(&self, limit: usize, mut budget: usize) -> Option<usize> {
+        if let Ok(board) = self.to_bitboard() {
+            return Some(board.count_solutions(limit));
+        }
+        let mut sudoku = self.clone();
+        let mut count = 0;
+        sudoku.count_search(limit, &mut count, &mut budget);
+        (budget > 0).then_some(count)
+    }
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+    fn count_search(&mut self, limit: usize, count: &mut usize, budget: &mut usize) {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+        self.calculate();
+        if self.is_error() {
+            return;
+        }
+        if self.remaining_count().0 == 0 {
+            if self.is_solved() {
+                *count += 1;
+            }
+            return;
+        }
+        let (pos, values) = match self.pick_cell() {
+            Some(p) => p,
+            None => return,
+        };
+        let store = self.snapshot();
+        for v in values {
+            self.set_value(pos, v as u8);
+            self.count_search(limit, count, budget);
+            self.restore(&store);
+            if *count >= limit || *budget == 0 {
+                return;
+            }
+        }
+    }
+    fn fill_random<R: Rng>(&mut self, rng: &mut R) -> bool {
+        self.calculate();
+        if self.is_error() {
+            return false;
+        }
+        if self.remaining_count().0 == 0 {
+            return self.is_solved();
+        }
+        let (pos, mut values) = match self.pick_cell() {
+            Some(p) => p,
+            None => return false,
+        };
+        values.shuffle(rng);
+        let store = self.snapshot();
+        for v in values {
+            self.set_value(pos, v as u8);
+            if self.fill_random(rng) {
+                return true;
+            }
+            self.restore(&store);
+        }
+        false
+    }
+    // replaces the board with a random minimal puzzle that has a unique solution
+    // under the current size and houses
+    pub fn generate(&mut self) -> bool {
+        let mut rng = thread_rng();
+        self.clean();
+        if !self.fill_random(&mut rng) {
+            return false;
+        }
+        self.remove_givens(&mut rng);
+        true
+    }
+    // like generate, but first marks every neighbouring pair of the random
+    // solution that fits one of kinds; inequality signs only go inside blocks
+    // as in Greater-Than Sudoku, and the negative rules that are switched on
+    // get all of their markings
+    pub fn generate_edges(&mut self, kinds: &[EdgeKind]) -> bool {
+        let mut rng = thread_rng();
+        let mut kinds = kinds.to_vec();
+        if self.kropki_negative {
+            kinds.extend([EdgeKind::White, EdgeKind::Black]);
+        }
+        if self.xv_negative {
+            kinds.extend([EdgeKind::X, EdgeKind::V]);
+        }
+        let negative = (self.kropki_negative, self.xv_negative);
+        self.kropki_negative = false;
+        self.xv_negative = false;
+        self.clear_edges();
+        self.clean();
+        let filled = self.fill_random(&mut rng);
+        (self.kropki_negative, self.xv_negative) = negative;
+        if !filled {
+            return false;
+        }
+        for r in 1..=self.size {
+            for c in 1..=self.size {
+                for b in [(r, c + 1), (r + 1, c)] {
+                    if b.0 > self.size || b.1 > self.size {
+                        continue;
+                    }
+                    let x = self.data[[r - 1, c - 1]].value as usize;
+                    let y = self.data[[b.0 - 1, b.1 - 1]].value as usize;
+                    let same_block = self.get_block_index((r, c)) == self.get_block_index(b);
+                    let edge = kinds.iter().find_map(|&kind| match kind {
+                        EdgeKind::Less if !same_block => None,
+                        EdgeKind::Less if x < y => Some(((r, c), b, kind)),
+                        EdgeKind::Less => Some((b, (r, c), kind)),
+                        _ if kind.allows(x, y) => Some(((r, c), b, kind)),
+                        _ => None,
+                    });
+                    if let Some((a, b, kind)) = edge {
+                        self.edges.push(Edge { a, b, kind });
+                    }
+                }
+            }
+        }
+        self.remove_givens(&mut rng);
+        true
+    }
+    // drops givens in random order as long as the solution stays unique
+    fn remove_givens<R: Rng>(&mut self, rng: &mut R) {
+        let mut givens: Vec<((usize, usize), u8)> = self
+            .data
+            .iter()
+            .map(|c| ((c.row_id, c.column_id), c.value))
+            .collect();
+        givens.shuffle(rng);
+        let mut kept = givens.clone();
+        // the solution is known, so it stays unique when no solution puts
+        // another digit in the emptied cell; one search instead of counting
+        // two, and a given whose check runs out of steps is kept
+        for (pos, value) in givens {
+            let rest: Vec<((usize, usize), u8)> =
+                kept.iter().filter(|g| g.0 != pos).copied().collect();
+            let mut puzzle = self.clone();
+            puzzle.clean();
+            for &(p, v) in &rest {
+                puzzle.set_value(p, v);
+            }
+            puzzle.data[[pos.0 - 1, pos.1 - 1]].clear_bit(value as usize - 1);
+            if puzzle.count_solutions_within(1, REMOVAL_STEPS) == Some(0) {
+                kept = rest;
+            }
+        }
+        self.clean();
+        for (p, v) in kept {
+            self.set_value(p, v);
+        }
+        self.mark_givens();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Sudoku;
+
+    // classic blocks with a cell swapped between neighbours in each band
+    const JIGSAW: &str = "111122333111222333112222333\
+                          444555566444555666444556666\
+                          777788999777888999778888999";
+
+    #[test]
+    fn generated_jigsaw_is_unique() {
+        let mut sudoku = Sudoku::new();
+        sudoku.set_jigsaw(JIGSAW).unwrap();
+        assert!(sudoku.generate());
+        assert!(sudoku.has_unique_solution());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::{Cell, Note, Rules, Sudoku, SudokuError};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum House {
+    Row(usize),
+    Column(usize),
+    Block(usize),
+    Diagonal(usize), // 1: top-left to bottom-right, 2: top-right to bottom-left
+    Region(usize),   // user-defined extra region, e.g. Windoku windows
+    Cage(usize),     // killer cage, no repeats but not every digit
+    Group(usize),    // no repeats but not every digit, e.g. Argyle lines
+}
+impl fmt::Display for House {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            House::Row(i) => write!(f, "行{}", i),
+            House::Column(i) => write!(f, "列{}", i),
+            House::Block(i) => write!(f, "块{}", i),
+            House::Diagonal(i) => write!(f, "对角线{}", i),
+            House::Region(i) => write!(f, "区域{}", i),
+            House::Cage(i) => write!(f, "笼子{}", i),
+            House::Group(i) => write!(f, "组{}", i),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    pub house: House,
+    pub value: u8,
+    pub cells: ((usize, usize), (usize, usize)),
+}
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, b) = self.cells;
+        write!(
+            f,
+            "{}中值{}重复: {}:{} 与 {}:{}",
+            self.house, self.value, a.0, a.1, b.0, b.1
+        )
+    }
+}
+
+impl Sudoku {
+    pub fn houses(&self) -> Vec<House> {
+        let mut houses = Vec::new();
+        for i in 1..=self.size {
+            houses.push(House::Row(i));
+        }
+        for i in 1..=self.size {
+            houses.push(House::Column(i));
+        }
+        for i in 1..=self.size {
+            houses.push(House::Block(i));
+        }
+        houses.extend(self.extra_houses());
+        houses
+    }
+    // houses beyond rows, columns and blocks
+    pub fn extra_houses(&self) -> Vec<House> {
+        let mut houses = Vec::new();
+        if self.diagonal {
+            houses.push(House::Diagonal(1));
+            houses.push(House::Diagonal(2));
+        }
+        for i in 1..=self.regions.len() {
+            houses.push(House::Region(i));
+        }
+        houses
+    }
+    // extra houses plus the groups that only forbid repeats, such as cages
+    pub fn peer_groups(&self) -> Vec<House> {
+        let mut groups = self.extra_houses();
+        for i in 1..=self.groups.len() {
+            groups.push(House::Group(i));
+        }
+        for i in 1..=self.cages.len() {
+            groups.push(House::Cage(i));
+        }
+        groups
+    }
+    pub(crate) fn house_contains(&self, house: House, pos: (usize, usize)) -> bool {
+        match house {
+            House::Row(i) => pos.0 == i,
+            House::Column(i) => pos.1 == i,
+            House::Block(i) => self.get_block_index(pos) == i,
+            House::Diagonal(1) => pos.0 == pos.1,
+            House::Diagonal(_) => pos.0 + pos.1 == self.size + 1,
+            House::Region(i) => self.regions[i - 1].contains(&pos),
+            House::Cage(i) => self.cages[i - 1].cells.contains(&pos),
+            House::Group(i) => self.groups[i - 1].contains(&pos),
+        }
+    }
+    // Sudoku-X: both main diagonals hold every digit once
+    pub fn set_diagonal(&mut self, diagonal: bool) {
+        self.diagonal = diagonal;
+        self.recompute_grids();
+    }
+    pub fn is_diagonal(&self) -> bool {
+        self.diagonal
+    }
+    pub fn get_diagonal(&self, diagonal: usize) -> Vec<&Cell> {
+        let mut result = Vec::new();
+        for i in 0..self.size {
+            match diagonal {
+                1 => result.push(&self.data[[i, i]]),
+                _ => result.push(&self.data[[i, self.size - 1 - i]]),
+            }
+        }
+        result
+    }
+    // an extra house of exactly size distinct cells, returns its region number
+    pub fn add_region(&mut self, cells: Vec<(usize, usize)>) -> Result<usize, SudokuError> {
+        for (i, &pos) in cells.iter().enumerate() {
+            self.try_get_cell(pos)?;
+            if cells[..i].contains(&pos) {
+                return Err(SudokuError::InvalidRegion(cells.len()));
+            }
+        }
+        if cells.len() != self.size {
+            return Err(SudokuError::InvalidRegion(cells.len()));
+        }
+        self.regions.push(cells);
+        self.apply_peers();
+        Ok(self.regions.len())
+    }
+    // Windoku: four 3x3 windows at rows/columns 2~4 and 6~8
+    pub fn add_windoku_regions(&mut self) -> Result<(), SudokuError> {
+        if self.size != 9 || self.box_rows != 3 {
+            return Err(SudokuError::InvalidSize(self.box_rows, self.box_columns));
+        }
+        for r in [2, 6] {
+            for c in [2, 6] {
+                let mut cells = Vec::new();
+                for i in r..r + 3 {
+                    for j in c..c + 3 {
+                        cells.push((i, j));
+                    }
+                }
+                self.add_region(cells)?;
+            }
+        }
+        Ok(())
+    }
+    // disjoint groups: the cells at the same place in every block form a house
+    pub fn add_disjoint_groups(&mut self) -> Result<(), SudokuError> {
+        if self.jigsaw.is_some() {
+            return Err(SudokuError::InvalidSize(self.box_rows, self.box_columns));
+        }
+        for i in 0..self.box_rows {
+            for j in 0..self.box_columns {
+                let mut cells = Vec::new();
+                for r in (i..self.size).step_by(self.box_rows) {
+                    for c in (j..self.size).step_by(self.box_columns) {
+                        cells.push((r + 1, c + 1));
+                    }
+                }
+                self.add_region(cells)?;
+            }
+        }
+        Ok(())
+    }
+    // a set of cells without repeats that need not hold every digit,
+    // returns its group number
+    pub fn add_group(&mut self, cells: Vec<(usize, usize)>) -> Result<usize, SudokuError> {
+        for (i, &pos) in cells.iter().enumerate() {
+            self.try_get_cell(pos)?;
+            if cells[..i].contains(&pos) {
+                return Err(SudokuError::InvalidRegion(cells.len()));
+            }
+        }
+        if cells.len() < 2 || cells.len() > self.size {
+            return Err(SudokuError::InvalidRegion(cells.len()));
+        }
+        self.groups.push(cells);
+        self.apply_peers();
+        Ok(self.groups.len())
+    }
+    // Argyle: the eight diagonal lines of the argyle pattern, two of length 8
+    // and two of length 5 in each direction
+    pub fn add_argyle_groups(&mut self) -> Result<(), SudokuError> {
+        if self.size != 9 {
+            return Err(SudokuError::InvalidSize(self.box_rows, self.box_columns));
+        }
+        for d in [1, 4] {
+            let down: Vec<(usize, usize)> = (1..=9 - d).map(|r| (r, r + d)).collect();
+            let up: Vec<(usize, usize)> = (1..=9 - d).map(|r| (r + d, r)).collect();
+            let left: Vec<(usize, usize)> = (1..=9 - d).map(|r| (r, 9 - d + 1 - r)).collect();
+            let right: Vec<(usize, usize)> = (1..=9 - d).map(|r| (r + d, 10 - r)).collect();
+            for cells in [down, up, left, right] {
+                self.add_group(cells)?;
+            }
+        }
+        Ok(())
+    }
+    pub fn clear_groups(&mut self) {
+        self.groups.clear();
+        self.recompute_grids();
+    }
+    pub fn get_group(&self, group: usize) -> Vec<&Cell> {
+        let mut result = Vec::new();
+        for &(r, c) in &self.groups[group - 1] {
+            result.push(&self.data[[r - 1, c - 1]]);
+        }
+        result
+    }
+    pub fn clear_regions(&mut self) {
+        self.regions.clear();
+        self.recompute_grids();
+    }
+    pub fn get_region(&self, region: usize) -> Vec<&Cell> {
+        let mut result = Vec::new();
+        for &(r, c) in &self.regions[region - 1] {
+            result.push(&self.data[[r - 1, c - 1]]);
+        }
+        result
+    }
+    // jigsaw blocks: one label per cell, row by row, each of the size labels
+    // covering size connected cells, e.g. "111222333..." for 9x9
+    pub fn set_jigsaw(&mut self, map: &str) -> Result<(), SudokuError> {
+        let size = self.size;
+        let chars: Vec<char> = map.chars().filter(|ch| !ch.is_whitespace()).collect();
+        if chars.len() != size * size {
+            return Err(SudokuError::InvalidLength(chars.len(), size * size));
+        }
+        let mut labels: Vec<char> = Vec::new();
+        for &ch in &chars {
+            if !labels.contains(&ch) {
+                labels.push(ch);
+            }
+        }
+        if labels.len() != size {
+            return Err(SudokuError::InvalidJigsaw(labels[labels.len() - 1]));
+        }
+        let mut blocks = vec![0; size * size];
+        for (index, ch) in chars.iter().enumerate() {
+            blocks[index] = labels.iter().position(|l| l == ch).unwrap() + 1;
+        }
+        for (i, &label) in labels.iter().enumerate() {
+            // flood fill from the first cell of the block
+            let start = blocks.iter().position(|&b| b == i + 1).unwrap();
+            let mut seen = vec![start];
+            let mut todo = vec![start];
+            while let Some(index) = todo.pop() {
+                let (r, c) = (index / size, index % size);
+                let mut next = Vec::new();
+                if r > 0 {
+                    next.push(index - size);
+                }
+                if r + 1 < size {
+                    next.push(index + size);
+                }
+                if c > 0 {
+                    next.push(index - 1);
+                }
+                if c + 1 < size {
+                    next.push(index + 1);
+                }
+                for n in next {
+                    if blocks[n] == i + 1 && !seen.contains(&n) {
+                        seen.push(n);
+                        todo.push(n);
+                    }
+                }
+            }
+            let count = blocks.iter().filter(|&&b| b == i + 1).count();
+            if count != size || seen.len() != size {
+                return Err(SudokuError::InvalidJigsaw(label));
+            }
+        }
+        self.jigsaw = Some(blocks);
+        self.recompute_grids();
+        Ok(())
+    }
+    pub fn clear_jigsaw(&mut self) {
+        self.jigsaw = None;
+        self.recompute_grids();
+    }
+    pub fn is_jigsaw(&self) -> bool {
+        self.jigsaw.is_some()
+    }
+    pub fn try_init_jigsaw(&mut self, puzzle: &str, map: &str) -> Result<(), SudokuError> {
+        self.set_jigsaw(map)?;
+        self.try_init(puzzle)
+    }
+    // cells whose right (true) or bottom (false) side is a block border,
+    // for drawing the grid
+    pub(crate) fn block_sides(&self) -> Vec<((usize, usize), bool)> {
+        let mut sides = Vec::new();
+        for r in 1..=self.size {
+            for c in 1..=self.size {
+                let block = self.get_block_index((r, c));
+                if c < self.size && block != self.get_block_index((r, c + 1)) {
+                    sides.push(((r, c), true));
+                }
+                if r < self.size && block != self.get_block_index((r + 1, c)) {
+                    sides.push(((r, c), false));
+                }
+            }
+        }
+        sides
+    }
+    // rows, columns and blocks only, the blocks may be jigsaw
+    pub(crate) fn fits_bitboard(&self) -> bool {
+        self.size == 9
+            && self.peer_groups().is_empty()
+            && self.rules == Rules::default()
+            && !self.has_pair_rules()
+            && self.lines.is_empty()
+            && self.parities.is_empty()
+            && self.outside.is_empty()
+    }
+    pub fn get_house(&self, house: House) -> Vec<&Cell> {
+        match house {
+            House::Row(i) => self.get_row(i).into_iter().collect(),
+            House::Column(i) => self.get_column(i).into_iter().collect(),
+            House::Block(i) => self.get_block(i).collect(),
+            House::Diagonal(i) => self.get_diagonal(i),
+            House::Region(i) => self.get_region(i),
+            House::Cage(i) => self.cages[i - 1]
+                .cells
+                .iter()
+                .map(|&(r, c)| &self.data[[r - 1, c - 1]])
+                .collect(),
+            House::Group(i) => self.get_group(i),
+        }
+    }
+    // hidden singles and naked pairs in the extra houses
+    pub fn check_extra_houses(&mut self) {
+        for house in self.extra_houses() {
+            let mut result = Vec::new();
+            let cells = self.get_house(house);
+            for i in 0..self.size {
+                let mut count = 0;
+                let mut pos = (0, 0);
+                for c in &cells {
+                    if c.value == 0 && c.check_bit(i) {
+                        pos = (c.row_id, c.column_id);
+                        count += 1;
+                    }
+                }
+                if count == 1 {
+                    result.push((pos, i as u8 + 1));
+                }
+            }
+            for (pos, value) in result {
+                if self.data[[pos.0 - 1, pos.1 - 1]].value != 0 {
+                    continue;
+                }
+                self.data[[pos.0 - 1, pos.1 - 1]]
+                    .log
+                    .push(Note::Hidden(house, value));
+                self.set_value(pos, value);
+            }
+            let mut clear = Vec::new();
+            let cells = self.get_house(house);
+            for (i, a) in cells.iter().enumerate() {
+                if a.value != 0 || a.count() != 2 {
+                    continue;
+                }
+                for b in &cells[i + 1..] {
+                    if b.value == 0 && a.grid == b.grid {
+                        for c in &cells {
+                            if c.value == 0 && c.grid != a.grid {
+                                for v in a.get_values() {
+                                    clear.push(((c.row_id, c.column_id), v - 1));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            for (p, bit) in clear {
+                self.data[[p.0 - 1, p.1 - 1]].clear_bit(bit);
+            }
+        }
+    }
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut result = Vec::new();
+        let mut houses = self.houses();
+        for i in 1..=self.groups.len() {
+            houses.push(House::Group(i));
+        }
+        for i in 1..=self.cages.len() {
+            houses.push(House::Cage(i));
+        }
+        for house in houses {
+            let cells = self.get_house(house);
+            for (i, a) in cells.iter().enumerate() {
+                if a.value == 0 {
+                    continue;
+                }
+                for b in &cells[i + 1..] {
+                    if a.value == b.value {
+                        result.push(Conflict {
+                            house,
+                            value: a.value,
+                            cells: ((a.row_id, a.column_id), (b.row_id, b.column_id)),
+                        });
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Sudoku;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn solvable(sudoku: &Sudoku) -> bool {
+        sudoku.count_solutions(2) == 1 && sudoku.clone().search()
+    }
+
+    // a house added and removed again leaves the classic puzzle solvable
+    #[test]
+    fn diagonal_turned_off_gives_back_candidates() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.set_diagonal(true);
+        sudoku.set_diagonal(false);
+        assert!(solvable(&sudoku));
+    }
+
+    #[test]
+    fn cleared_regions_and_groups_give_back_candidates() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.add_windoku_regions().unwrap();
+        sudoku.clear_regions();
+        assert!(solvable(&sudoku));
+        sudoku.add_argyle_groups().unwrap();
+        sudoku.clear_groups();
+        assert!(solvable(&sudoku));
+    }
+
+    // classic blocks with a cell swapped between neighbours in each band
+    const JIGSAW: &str = "111122333111222333112222333\
+                          444555566444555666444556666\
+                          777788999777888999778888999";
+
+    // givens placed before the jigsaw keep no eliminations from the 3x3 blocks
+    #[test]
+    fn jigsaw_set_after_givens() {
+        let mut solved = Sudoku::new();
+        solved.set_jigsaw(JIGSAW).unwrap();
+        assert!(solved.search());
+        let values: Vec<u8> = solved.data.iter().map(|c| c.value).collect();
+        let givens: String = values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| if i % 3 == 0 { (b'0' + v) as char } else { '0' })
+            .collect();
+        let mut sudoku = Sudoku::new();
+        sudoku.init(&givens);
+        sudoku.set_jigsaw(JIGSAW).unwrap();
+        for (c, &v) in sudoku.data.iter().zip(&values) {
+            assert!(c.check_bit(v as usize - 1));
+        }
+    }
+
+    #[test]
+    fn cleared_jigsaw_gives_back_candidates() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.set_jigsaw(JIGSAW).unwrap();
+        sudoku.clear_jigsaw();
+        assert!(solvable(&sudoku));
+    }
+}
@@ -0,0 +1,506 @@
+use crate::{Sudoku, SudokuError};
+use std::collections::VecDeque;
+
+// Digits for drawing boards and reading photos of them: each glyph is
+// GLYPH_HEIGHT rows of its width in pixels, a row as six hex digits with the
+// left-most pixel in the highest used bit. The first face draws, all of them
+// are the templates for recognition.
+const GLYPH_HEIGHT: usize = 24;
+const GLYPHS: [(u8, usize, &str); 36] = [
+    // DejaVu Sans
+    (1, 14, "0007e0003fe0003fe00038e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e00000e0001fff003fff003fff"),
+    (2, 15, "000fe0003ff8007ffc00701e00000e00000f00000700000700000f00000e00001e00003c0000380000700000e00001e00003c0000780000f00001e00003c00007fff007fff007fff"),
+    (3, 15, "000fe0003ff8003ffe00201e00000f00000f00000f00000e00000e00003c0007f80007f80007fc00001e00000f00000700000700000700000700000f00401e007ffe007ff8001fe0"),
+    (4, 17, "0000780000f80001f80001f80003b8000738000738000e38001c38001c3800383800703800703800e03801c03801c03801ffff01ffff000038000038000038000038000038000038"),
+    (5, 15, "003ffe003ffe003ffc003800003800003800003800003800003fe0003ff8003ffc00201e00000f00000f00000700000700000700000700000f00000f00601e007ffc007ff8001fe0"),
+    (6, 16, "0003f8000ffe001ffe003c0000380000780000700000700000e1e000e7f800fffc00fc1e00f80e00f00f00f00700f00700f00700700700700f00780e00381e001ffc000ff80007e0"),
+    (7, 15, "007fff007fff007fff00000f00000e00001e00001c00001c00003c0000380000380000780000700000f00000e00000e00001e00001c00003c0000380000380000780000700000700"),
+    (8, 16, "0007e0001ff8003ffc00781e00700e00f00e00f00e00700e00700e00383c001ff8000ff0003ffc00781e00f00e00e00f00e00700e00700e00700f00f00781e007ffe003ff8000fe0"),
+    (9, 16, "0007e0001ff0003ff800781c00701e00f00e00e00e00e00f00e00f00e00f00f00f00701f00783f003fff001fe700078f00000e00000e00001e00001c00003c007ff8007ff0001fc0"),
+    // DejaVu Sans Bold
+    (1, 17, "003fe001ffe001ffe001ffe001efe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe0000fe001ffff01ffff01ffff01ffff"),
+    (2, 17, "003fe001fff801fffc01fffe01f0ff01807f00003f00003f00003f00007f00007e0000fc0001fc0003f80007e0000fc0003f80007f0000fe0001ffff01ffff01ffff01ffff01ffff"),
+    (3, 17, "003fe000fffc00fffe00fffe00e0ff00007f00003f00003f00007e0001fe001ff8001ff8001ffe001ffe00007f00003f00003f00003f01003f01c0ff01ffff01fffe01fff8007fe0"),
+    (4, 20, "0007f80007f8000ff8001ff8001ff8003ff8007df80079f800f1f801f1f801e1f803c1f807c1f80781f80f01f80fffff0fffff0fffff0fffff0fffff0001f80001f80001f80001f8"),
+    (5, 18, "01fffc01fffc01fffc01fffc01fffc01f00001f00001f00001ffe001fff801fffc01fffe0180fe00007e00007f00003f00003f00007f03007e03c1fe03fffc03fff803fff0007fc0"),
+    (6, 18, "0007f8001ffe003ffe007ffe00fe0601f80001f80001f00003f3e003fff803fffc03fffe03fc7f03f83f03f83f03f81f03f81f01f81f01f83f01f83f00fffe007ffc003ff8000fe0"),
+    (7, 18, "03ffff03ffff03ffff03ffff03ffff00007e00007e0000fc0000fc0001f80001f80003f00003f00007f00007e00007e0000fc0000fc0001f80001f80003f00003f00007e00007e00"),
+    (8, 18, "001fe0007ffc00fffe01fffe01f87f03f03f03f03f01f03f01f87e00fffc007ff8003ff000fffc01fc7e03f03f03f03f03f03f03f03f03f03f03f87f01fffe01fffe007ffc001fe0"),
+    (9, 18, "001fc0007ff000fff801fffc01f87e03f03e03f03f03f03f03f03f03f03f03f07f01f8ff01ffff00ffff007fff001f1f00003f00003e00007e0180fc01fffc01fff801ffe0007f80"),
+    // DejaVu Serif
+    (1, 12, "0000700001f00003f0000f70000c700008700000700000700000700000700000700000700000700000700000700000700000700000700000700000700000700000700007ff0007ff"),
+    (2, 15, "000fe0003ff800781c00700e00600f00600f00000f00000f00000f00000e00000e00001c0000380000300000600000c0000180000300000601000c03001803003fff007fff007fff"),
+    (3, 15, "000ff0003ffc00381e00300e00300e00200f00000e00000e00000e00003c0003f00003f800001c00000e00000700000700000700000700600700600700700f00781e007ffc000ff0"),
+    (4, 18, "0000f00001f00001f00003f00007f00006f0000cf0001cf00018f00030f00070f00060f000e0f000c0f00180f00380f003ffff03ffff0000f00000f00000f00000f00007fe000ffe"),
+    (5, 15, "003ffe003ffe003ffc0030000030000030000030000030000033e0003ff800381c00300e00000f00000700000700000700000700000700600700600f00700e00781c007ff8000fe0"),
+    (6, 16, "0003f8000ffe001c0e00380600300600700000700000700000e0c000e7f800fc3c00f81e00f00e00f00f00f00700f00700f00700f00700700700700f00380e003c1c001ff80007e0"),
+    (7, 16, "00ffff00ffff00fffe00c00600c00e00c00c00001c0000180000380000380000300000700000600000e00000c00000c00001c0000180000380000300000700000600000600000e00"),
+    (8, 16, "0007e0001ff800381c00701e00700e00700e00700e00700e00701e00381c001ff8000ff0003c3c00700e00700e00f00f00e00700e00700e00f00f00f00700e00781e003ffc000ff0"),
+    (9, 16, "0007e0001ff800381c00701c00700e00f00e00e00f00e00f00e00f00e00f00f00f00700f00781f003c3f001fe700038700000600000e00000e00600c00601c007038007ff0001fc0"),
+    // DejaVu Sans Mono
+    (1, 14, "0007e0003fe0003fe00039e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e00001e0001fff003fff003fff"),
+    (2, 14, "0007f0003ffc003ffe00381e00000f00000700000700000700000700000f00000e00001c00003c0000780000f00001e00001c0000380000700000e00001c00003fff003fff003fff"),
+    (3, 15, "000fe0003ff8003ffc00201e00001e00000e00000e00000e00001e00003c0007f80007f00007fc00001e00000e00000e00000f00000f00000f00000e00601e007ffc007ff8001fe0"),
+    (4, 17, "0000f80000f80001f80003f8000378000778000e78000e78001c7800187800387800707800707800e07801c07801c07801ffff01ffff000078000078000078000078000078000078"),
+    (5, 15, "003ffc003ffc003ffc003800003800003800003800003800003fe0003ff8003ffc00203e00001e00000e00000f00000f00000f00000f00000e00001e00603e007ffc007ff8003fe0"),
+    (6, 15, "0001f80007fc000ffc001e00003c000038000038000070000071e00077f8007ffc007c1e00780e00780700780700780700780700780700380700380e003c0e001ffc000ffc0003f0"),
+    (7, 15, "007fff007fff007fff00000e00000e00001e00001c00001c0000380000380000780000700000700000f00000e00001e00001c00001c00003c0000380000780000700000700000f00"),
+    (8, 15, "0007f0000ffc001ffc003c1e00380e00780f00780f00380e00380e001c1c000ff80007f0001ffc003c1e00380f00700700700700700700700700780f00380e003ffe001ffc0007f0"),
+    (9, 15, "0007e0001ff8003ffc00381e00780e00700e00700f00700f00700f00700f00700f00380f003c1f001fff000ff70003c700000700000e00000e00001e00003c001ff8001ff0000fc0"),
+];
+
+// ink density grid compared between a cell and the templates
+const FEATURE_WIDTH: usize = 8;
+const FEATURE_HEIGHT: usize = 10;
+type Features = [f32; FEATURE_WIDTH * FEATURE_HEIGHT];
+
+fn glyph_ink(hex: &str, width: usize, x: usize, y: usize) -> bool {
+    let row = u32::from_str_radix(&hex[y * 6..y * 6 + 6], 16).unwrap_or(0);
+    (row >> (width - 1 - x)) & 1 == 1
+}
+
+// the ink of a width x height box, scaled to the feature grid height (or its
+// width for wide shapes) and centred, so a narrow 1 stays narrow
+fn features(width: usize, height: usize, ink: impl Fn(usize, usize) -> bool) -> Features {
+    let scale = (height as f32 / FEATURE_HEIGHT as f32).max(width as f32 / FEATURE_WIDTH as f32);
+    let left = (FEATURE_WIDTH as f32 * scale - width as f32) / 2.0;
+    let top = (FEATURE_HEIGHT as f32 * scale - height as f32) / 2.0;
+    let mut result = [0.0; FEATURE_WIDTH * FEATURE_HEIGHT];
+    for (i, density) in result.iter_mut().enumerate() {
+        let (fx, fy) = (i % FEATURE_WIDTH, i / FEATURE_WIDTH);
+        let mut count = 0;
+        for sy in 0..4 {
+            for sx in 0..4 {
+                let x = (fx as f32 + (sx as f32 + 0.5) / 4.0) * scale - left;
+                let y = (fy as f32 + (sy as f32 + 0.5) / 4.0) * scale - top;
+                if x >= 0.0 && y >= 0.0 && ink(x as usize, y as usize) {
+                    count += 1;
+                }
+            }
+        }
+        *density = count as f32 / 16.0;
+    }
+    result
+}
+
+fn templates() -> Vec<(u8, Features)> {
+    GLYPHS
+        .iter()
+        .map(|&(digit, width, hex)| {
+            let ink =
+                |x: usize, y: usize| x < width && y < GLYPH_HEIGHT && glyph_ink(hex, width, x, y);
+            (digit, features(width, GLYPH_HEIGHT, ink))
+        })
+        .collect()
+}
+
+// nearest neighbour among the templates
+fn classify(templates: &[(u8, Features)], cell: &Features) -> u8 {
+    let distance =
+        |t: &Features| -> f32 { t.iter().zip(cell).map(|(a, b)| (a - b) * (a - b)).sum() };
+    templates
+        .iter()
+        .min_by(|a, b| distance(&a.1).total_cmp(&distance(&b.1)))
+        .map(|t| t.0)
+        .unwrap_or(0)
+}
+
+// a grayscale picture, 0 black to 255 white
+struct Gray {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+// PNG or JPEG by their signatures, transparent pixels on white
+fn decode(bytes: &[u8]) -> Option<Gray> {
+    if bytes.starts_with(b"\x89PNG") {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().ok()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).ok()?;
+        let channels = info.color_type.samples();
+        let over_white =
+            |v: u8, a: u8| ((v as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|p| match p.len() {
+                1 => p[0],
+                2 => over_white(p[0], p[1]),
+                3 => luma(p[0], p[1], p[2]),
+                _ => over_white(luma(p[0], p[1], p[2]), p[3]),
+            })
+            .collect();
+        return Some(Gray {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        });
+    }
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let data = decoder.decode().ok()?;
+        let info = decoder.info()?;
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::L8 => data,
+            jpeg_decoder::PixelFormat::L16 => data.chunks_exact(2).map(|p| p[0]).collect(),
+            jpeg_decoder::PixelFormat::RGB24 => data
+                .chunks_exact(3)
+                .map(|p| luma(p[0], p[1], p[2]))
+                .collect(),
+            jpeg_decoder::PixelFormat::CMYK32 => data
+                .chunks_exact(4)
+                .map(|p| {
+                    let k = 255 - p[3] as u32;
+                    let rgb = [p[0], p[1], p[2]].map(|c| ((255 - c as u32) * k / 255) as u8);
+                    luma(rgb[0], rgb[1], rgb[2])
+                })
+                .collect(),
+        };
+        return Some(Gray {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        });
+    }
+    None
+}
+
+// Otsu's threshold between ink and paper
+fn threshold(pixels: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for &p in pixels {
+        histogram[p as usize] += 1;
+    }
+    let total = pixels.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| i as f64 * n as f64)
+        .sum();
+    let (mut below, mut below_sum) = (0.0, 0.0);
+    let (mut best, mut best_variance) = (128, 0.0);
+    for (t, &n) in histogram.iter().enumerate() {
+        below += n as f64;
+        below_sum += t as f64 * n as f64;
+        if below == 0.0 || below == total {
+            continue;
+        }
+        let above = total - below;
+        let difference = below_sum / below - (sum - below_sum) / above;
+        let variance = below * above * difference * difference;
+        if variance > best_variance {
+            best_variance = variance;
+            best = t as u8 + 1;
+        }
+    }
+    best
+}
+
+// the first and last index of the grid, from the indices whose longest ink
+// run is at least 60% of the longest anywhere; these lines must each sit on
+// a ninth of the span, with the block lines at a third and two thirds, so
+// grids of other sizes are not split into 9x9
+fn grid_span(runs: &[usize]) -> Option<(usize, usize)> {
+    let longest = *runs.iter().max()?;
+    let mut lines: Vec<(usize, usize)> = Vec::new();
+    for (i, &n) in runs.iter().enumerate() {
+        if n * 10 < longest * 6 {
+            continue;
+        }
+        match lines.last_mut() {
+            Some(line) if line.1 + 1 == i => line.1 = i,
+            _ => lines.push((i, i)),
+        }
+    }
+    let (first, last) = (lines.first()?.0, lines.last()?.1);
+    let ninth = (last - first) as f32 / 9.0;
+    let mut steps = Vec::new();
+    for &(a, b) in &lines {
+        let at = ((a + b) as f32 / 2.0 - first as f32) / ninth;
+        if (at - at.round()).abs() > 0.2 {
+            return None;
+        }
+        steps.push(at.round() as usize);
+    }
+    match steps.contains(&3) && steps.contains(&6) {
+        true => Some((first, last)),
+        false => None,
+    }
+}
+
+fn longest_run(ink: impl Iterator<Item = bool>) -> usize {
+    let (mut run, mut longest) = (0, 0);
+    for dark in ink {
+        run = if dark { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+impl Sudoku {
+    // Reads a 9x9 puzzle from a PNG or JPEG of a clean, axis-aligned grid,
+    // e.g. a screenshot or a scan, as 81 characters for init, '0' for blanks.
+    // The grid is found by its outer lines and split evenly; in every cell the
+    // largest ink blob is compared with the bundled digit templates.
+    pub fn recognize_image(bytes: &[u8]) -> Result<String, SudokuError> {
+        let image = decode(bytes).ok_or(SudokuError::InvalidImage)?;
+        let (w, h) = (image.width, image.height);
+        let level = threshold(&image.pixels);
+        let dark = |x: usize, y: usize| image.pixels[y * w + x] < level;
+        let rows: Vec<usize> = (0..h)
+            .map(|y| longest_run((0..w).map(|x| dark(x, y))))
+            .collect();
+        let columns: Vec<usize> = (0..w)
+            .map(|x| longest_run((0..h).map(|y| dark(x, y))))
+            .collect();
+        let (top, bottom) = grid_span(&rows).ok_or(SudokuError::InvalidImage)?;
+        let (left, right) = grid_span(&columns).ok_or(SudokuError::InvalidImage)?;
+        let (grid_w, grid_h) = (right - left + 1, bottom - top + 1);
+        if grid_w < 9 * 8 || grid_h < 9 * 8 || grid_w * 3 > grid_h * 4 || grid_h * 3 > grid_w * 4 {
+            return Err(SudokuError::InvalidImage);
+        }
+        let templates = templates();
+        let (cell_w, cell_h) = (grid_w as f32 / 9.0, grid_h as f32 / 9.0);
+        let mut result = String::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                // inside the cell, clear of the grid lines
+                let x0 = (left as f32 + (c as f32 + 0.1) * cell_w) as usize;
+                let y0 = (top as f32 + (r as f32 + 0.1) * cell_h) as usize;
+                let x1 = (left as f32 + (c as f32 + 0.9) * cell_w) as usize;
+                let y1 = (top as f32 + (r as f32 + 0.9) * cell_h) as usize;
+                let blob = largest_blob(x0, y0, x1, y1, &dark, cell_w.min(cell_h));
+                result.push(match blob {
+                    // pencil marks and specks are too small for a digit
+                    Some((bx, by, bw, bh, mask)) if bh as f32 >= 0.3 * cell_h => {
+                        let ink = |x: usize, y: usize| {
+                            x < bw && y < bh && mask.contains(&(bx + x, by + y))
+                        };
+                        (b'0' + classify(&templates, &features(bw, bh, ink))) as char
+                    }
+                    _ => '0',
+                });
+            }
+        }
+        Ok(result)
+    }
+    // a PNG of the values, cell pixels per cell, givens in black and solved
+    // values in gray; only digits 1~9 are drawn. Cells are at least
+    // GLYPH_HEIGHT pixels, smaller digits are not recognized reliably
+    pub fn to_png(&self, cell: usize) -> Vec<u8> {
+        let cell = cell.max(GLYPH_HEIGHT);
+        let margin = cell / 2;
+        let side = self.size * cell + 2 * margin;
+        let thick = (cell / 14).max(2);
+        let mut pixels = vec![255u8; side * side];
+        let mut fill = |x0: usize, y0: usize, x1: usize, y1: usize, v: u8| {
+            for y in y0..y1.min(side) {
+                for x in x0..x1.min(side) {
+                    pixels[y * side + x] = pixels[y * side + x].min(v);
+                }
+            }
+        };
+        let end = margin + self.size * cell;
+        for i in 0..=self.size {
+            let at = margin + i * cell;
+            fill(margin, at, end + 1, at + 1, 160);
+            fill(at, margin, at + 1, end + 1, 160);
+        }
+        let half = thick / 2;
+        for ((r, c), right) in self.block_sides() {
+            let (x, y) = (margin + (c - 1) * cell, margin + (r - 1) * cell);
+            match right {
+                true => fill(
+                    x + cell - half,
+                    y - half,
+                    x + cell - half + thick,
+                    y + cell + half + 1,
+                    0,
+                ),
+                false => fill(
+                    x - half,
+                    y + cell - half,
+                    x + cell + half + 1,
+                    y + cell - half + thick,
+                    0,
+                ),
+            }
+        }
+        for at in [margin, end] {
+            fill(
+                margin - half,
+                at - half,
+                end - half + thick,
+                at - half + thick,
+                0,
+            );
+            fill(
+                at - half,
+                margin - half,
+                at - half + thick,
+                end - half + thick,
+                0,
+            );
+        }
+        let height = cell * 3 / 5;
+        for c in &self.data {
+            let Some(&(_, width, hex)) = GLYPHS[..9].get((c.value as usize).wrapping_sub(1)) else {
+                continue;
+            };
+            let shade = if c.given { 0 } else { 90 };
+            let width = width * height / GLYPH_HEIGHT;
+            let x0 = margin + (c.column_id - 1) * cell + (cell - width) / 2;
+            let y0 = margin + (c.row_id - 1) * cell + (cell - height) / 2;
+            for y in 0..height {
+                for x in 0..width {
+                    let gx = x * GLYPH_HEIGHT / height;
+                    let gy = y * GLYPH_HEIGHT / height;
+                    if glyph_ink(hex, GLYPHS[c.value as usize - 1].1, gx, gy) {
+                        fill(x0 + x, y0 + y, x0 + x + 1, y0 + y + 1, shade);
+                    }
+                }
+            }
+        }
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        if let Ok(mut writer) = encoder.write_header() {
+            let _ = writer.write_image_data(&pixels);
+        }
+        png
+    }
+}
+
+// the biggest 8-connected ink blob in a box: its corner, size and pixels
+type Blob = (
+    usize,
+    usize,
+    usize,
+    usize,
+    std::collections::HashSet<(usize, usize)>,
+);
+fn largest_blob(
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+    dark: &impl Fn(usize, usize) -> bool,
+    cell: f32,
+) -> Option<Blob> {
+    let mut seen = std::collections::HashSet::new();
+    let mut best: Option<Blob> = None;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if !dark(x, y) || seen.contains(&(x, y)) {
+                continue;
+            }
+            let mut blob = std::collections::HashSet::new();
+            let mut queue = VecDeque::from([(x, y)]);
+            seen.insert((x, y));
+            while let Some((px, py)) = queue.pop_front() {
+                blob.insert((px, py));
+                for (dx, dy) in [
+                    (-1, -1),
+                    (0, -1),
+                    (1, -1),
+                    (-1, 0),
+                    (1, 0),
+                    (-1, 1),
+                    (0, 1),
+                    (1, 1),
+                ] {
+                    let (nx, ny) = (px as isize + dx, py as isize + dy);
+                    if nx < x0 as isize
+                        || ny < y0 as isize
+                        || nx >= x1 as isize
+                        || ny >= y1 as isize
+                    {
+                        continue;
+                    }
+                    let next = (nx as usize, ny as usize);
+                    if dark(next.0, next.1) && seen.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+            let bx = blob.iter().map(|p| p.0).min().unwrap_or(x);
+            let by = blob.iter().map(|p| p.1).min().unwrap_or(y);
+            let bw = blob.iter().map(|p| p.0).max().unwrap_or(x) - bx + 1;
+            let bh = blob.iter().map(|p| p.1).max().unwrap_or(y) - by + 1;
+            // leftovers of a grid line are long and thin
+            let thin = (bw.min(bh) as f32) < (0.08 * cell).max(2.0);
+            if thin && bw.max(bh) as f32 > 0.5 * cell {
+                continue;
+            }
+            if best.as_ref().is_none_or(|b| b.4.len() < blob.len()) {
+                best = Some((bx, by, bw, bh, blob));
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GLYPH_HEIGHT;
+    use crate::{Sudoku, SudokuError};
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn values(sudoku: &Sudoku) -> String {
+        sudoku
+            .data
+            .iter()
+            .map(|c| (b'0' + c.value) as char)
+            .collect()
+    }
+
+    #[test]
+    fn puzzle_round_trip() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        let png = sudoku.to_png(30);
+        assert_eq!(Sudoku::recognize_image(&png).unwrap(), PUZZLE);
+    }
+
+    // sizes below the minimum are raised to it
+    #[test]
+    fn smallest_cells_round_trip() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        let png = sudoku.to_png(1);
+        assert_eq!(png, sudoku.to_png(GLYPH_HEIGHT));
+        assert_eq!(Sudoku::recognize_image(&png).unwrap(), PUZZLE);
+        assert!(sudoku.solve_fast());
+        let png = sudoku.to_png(1);
+        assert_eq!(Sudoku::recognize_image(&png).unwrap(), values(&sudoku));
+    }
+
+    #[test]
+    fn solved_round_trip() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        assert!(sudoku.solve_fast());
+        let png = sudoku.to_png(30);
+        assert_eq!(Sudoku::recognize_image(&png).unwrap(), values(&sudoku));
+    }
+
+    #[test]
+    fn other_sizes_rejected() {
+        for (rows, columns) in [(2, 2), (2, 3), (3, 4)] {
+            let png = Sudoku::with_size(rows, columns).unwrap().to_png(30);
+            assert!(matches!(
+                Sudoku::recognize_image(&png),
+                Err(SudokuError::InvalidImage)
+            ));
+        }
+    }
+}
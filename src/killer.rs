@@ -0,0 +1,285 @@
+use crate::{value_char, Note, Sudoku, SudokuError};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cage {
+    pub sum: usize,
+    pub cells: Vec<(usize, usize)>,
+}
+
+fn digit_sum(mask: u32) -> usize {
+    (0..32)
+        .filter(|i| mask & (1 << i) != 0)
+        .map(|i| i + 1)
+        .sum()
+}
+
+impl Sudoku {
+    // killer cage: distinct digits adding up to sum, returns the cage number
+    pub fn add_cage(
+        &mut self,
+        sum: usize,
+        cells: Vec<(usize, usize)>,
+    ) -> Result<usize, SudokuError> {
+        for (i, &pos) in cells.iter().enumerate() {
+            self.try_get_cell(pos)?;
+            if cells[..i].contains(&pos) || self.cage_of(pos).is_some() {
+                return Err(SudokuError::InvalidCage(sum));
+            }
+        }
+        let n = cells.len();
+        let min = n * (n + 1) / 2;
+        let max = n * (2 * self.size + 1 - n) / 2;
+        if n == 0 || n > self.size || sum < min || sum > max {
+            return Err(SudokuError::InvalidCage(sum));
+        }
+        self.cages.push(Cage { sum, cells });
+        self.apply_peers();
+        Ok(self.cages.len())
+    }
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+    pub fn clear_cages(&mut self) {
+        self.cages.clear();
+        self.recompute_grids();
+    }
+    pub fn cage_of(&self, pos: (usize, usize)) -> Option<usize> {
+        self.cages
+            .iter()
+            .position(|cage| cage.cells.contains(&pos))
+            .map(|i| i + 1)
+    }
+    // every cage that is completely filled adds up to its sum
+    pub(crate) fn cage_sums_match(&self) -> bool {
+        self.cages.iter().all(|cage| {
+            let values: Vec<usize> = cage
+                .cells
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].value as usize)
+                .collect();
+            values.contains(&0) || values.iter().sum::<usize>() == cage.sum
+        })
+    }
+    // keep only the candidates that appear in some distinct-digit filling of the cage
+    pub fn check_cages(&mut self) {
+        for i in 0..self.cages.len() {
+            let cage = &self.cages[i];
+            let grids: Vec<u32> = cage
+                .cells
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].grid)
+                .collect();
+            let allowed = cage_options(&grids, cage.sum);
+            let cells = cage.cells.clone();
+            for (&(r, c), mask) in cells.iter().zip(allowed) {
+                for bit in 0..self.size {
+                    if mask & (1 << bit) == 0 && self.data[[r - 1, c - 1]].check_bit(bit) {
+                        self.data[[r - 1, c - 1]].log.push(Note::Text(format!(
+                            "笼子{}的和为{}, 值{}不可能",
+                            i + 1,
+                            self.cages[i].sum,
+                            value_char(bit as u8 + 1)
+                        )));
+                        self.data[[r - 1, c - 1]].clear_bit(bit);
+                    }
+                }
+            }
+        }
+    }
+    // 45 rule: a set of whole houses adds up to a known total, so the cells
+    // left over by the cages inside it (innies) and the cells poking out of
+    // the cages crossing it (outies) have known sums too
+    pub fn check_innies_outies(&mut self) {
+        if self.cages.is_empty() {
+            return;
+        }
+        let size = self.size;
+        let total = size * (size + 1) / 2;
+        let mut areas: Vec<(Vec<(usize, usize)>, usize)> = Vec::new();
+        for first in 1..=size {
+            for last in first..=size {
+                let mut rows = Vec::new();
+                let mut columns = Vec::new();
+                for i in first..=last {
+                    for j in 1..=size {
+                        rows.push((i, j));
+                        columns.push((j, i));
+                    }
+                }
+                areas.push((rows, (last - first + 1) * total));
+                areas.push((columns, (last - first + 1) * total));
+            }
+        }
+        for b in 1..=size {
+            let cells = self.get_block(b).map(|c| (c.row_id, c.column_id)).collect();
+            areas.push((cells, total));
+        }
+        let mut sums = Vec::new();
+        for (area, area_total) in areas {
+            let mut in_area = vec![false; size * size];
+            for &(r, c) in &area {
+                in_area[(r - 1) * size + c - 1] = true;
+            }
+            let mut inside_sum = 0;
+            let mut crossing = Vec::new();
+            for (i, cage) in self.cages.iter().enumerate() {
+                let count = cage
+                    .cells
+                    .iter()
+                    .filter(|&&(r, c)| in_area[(r - 1) * size + c - 1])
+                    .count();
+                if count == cage.cells.len() {
+                    inside_sum += cage.sum;
+                    for &(r, c) in &cage.cells {
+                        in_area[(r - 1) * size + c - 1] = false;
+                    }
+                } else if count > 0 {
+                    crossing.push(i);
+                }
+            }
+            // what is still marked are the innies
+            let rest: Vec<(usize, usize)> = area
+                .iter()
+                .filter(|&&(r, c)| in_area[(r - 1) * size + c - 1])
+                .copied()
+                .collect();
+            if rest.is_empty() || rest.len() == area.len() || inside_sum > area_total {
+                continue;
+            }
+            let rest_sum = area_total - inside_sum;
+            let covered = rest
+                .iter()
+                .all(|p| crossing.iter().any(|&i| self.cages[i].cells.contains(p)));
+            if covered {
+                let crossing_sum: usize = crossing.iter().map(|&i| self.cages[i].sum).sum();
+                let mut outies = Vec::new();
+                for &i in &crossing {
+                    for &(r, c) in &self.cages[i].cells {
+                        if !area.contains(&(r, c)) {
+                            outies.push((r, c));
+                        }
+                    }
+                }
+                if crossing_sum >= rest_sum {
+                    sums.push((outies, crossing_sum - rest_sum));
+                }
+            }
+            sums.push((rest, rest_sum));
+        }
+        for (cells, sum) in sums {
+            self.restrict_sum(&cells, sum, "45法则");
+        }
+    }
+    // bounds check for cells whose values add up to sum, digits may repeat
+    pub(crate) fn restrict_sum(&mut self, cells: &[(usize, usize)], sum: usize, reason: &str) {
+        let bounds: Vec<(usize, usize)> = cells
+            .iter()
+            .map(|&(r, c)| {
+                let grid = self.data[[r - 1, c - 1]].grid;
+                match grid {
+                    0 => (0, 0),
+                    _ => (
+                        grid.trailing_zeros() as usize + 1,
+                        32 - grid.leading_zeros() as usize,
+                    ),
+                }
+            })
+            .collect();
+        let min: usize = bounds.iter().map(|b| b.0).sum();
+        let max: usize = bounds.iter().map(|b| b.1).sum();
+        for (i, &(r, c)) in cells.iter().enumerate() {
+            // the others can take between min - own min and max - own max
+            let low = sum as i64 - (max - bounds[i].1) as i64;
+            let high = sum as i64 - (min - bounds[i].0) as i64;
+            for bit in 0..self.size {
+                let v = bit as i64 + 1;
+                if (v < low || v > high) && self.data[[r - 1, c - 1]].check_bit(bit) {
+                    self.data[[r - 1, c - 1]].log.push(Note::Text(format!(
+                        "{}: 这些格子的和为{}, 值{}不可能",
+                        reason,
+                        sum,
+                        value_char(bit as u8 + 1)
+                    )));
+                    self.data[[r - 1, c - 1]].clear_bit(bit);
+                }
+            }
+        }
+    }
+}
+
+// allowed digits per cell, from the sets of used digits reachable cell by cell
+pub(crate) fn cage_options(grids: &[u32], sum: usize) -> Vec<u32> {
+    let mut layers: Vec<Vec<u32>> = vec![vec![0]];
+    for &grid in grids {
+        let mut next = Vec::new();
+        for &used in layers.last().unwrap() {
+            let mut free = grid & !used;
+            while free != 0 {
+                let bit = free & free.wrapping_neg();
+                free ^= bit;
+                next.push(used | bit);
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        layers.push(next);
+    }
+    let mut good: HashSet<u32> = layers
+        .last()
+        .unwrap()
+        .iter()
+        .filter(|&&used| digit_sum(used) == sum)
+        .copied()
+        .collect();
+    let mut allowed = vec![0; grids.len()];
+    for i in (0..grids.len()).rev() {
+        let mut prev = HashSet::new();
+        for &used in &layers[i] {
+            let mut free = grids[i] & !used;
+            while free != 0 {
+                let bit = free & free.wrapping_neg();
+                free ^= bit;
+                if good.contains(&(used | bit)) {
+                    allowed[i] |= bit;
+                    prev.insert(used);
+                }
+            }
+        }
+        good = prev;
+    }
+    allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Sudoku;
+
+    fn candidates(sudoku: &Sudoku, pos: (usize, usize)) -> Vec<usize> {
+        sudoku.try_get_cell(pos).unwrap().get_values()
+    }
+
+    // row 1 adds up to 10 and the cage inside it to 3, so the innies
+    // r1c3 and r1c4 add up to 7 and neither can be 1 or 2
+    #[test]
+    fn innies_take_the_rest_of_the_house() {
+        let mut sudoku = Sudoku::with_size(2, 2).unwrap();
+        sudoku.add_cage(3, vec![(1, 1), (1, 2)]).unwrap();
+        sudoku.add_cage(5, vec![(1, 3), (2, 3)]).unwrap();
+        sudoku.check_innies_outies();
+        assert_eq!(candidates(&sudoku, (1, 3)), vec![3, 4]);
+        assert_eq!(candidates(&sudoku, (1, 4)), vec![3, 4]);
+    }
+
+    // the innies of row 1 add up to 7 and lie in a cage of 9 that pokes out
+    // to r2c4, so that outie is 2
+    #[test]
+    fn outies_take_what_the_cage_has_left() {
+        let mut sudoku = Sudoku::with_size(2, 2).unwrap();
+        sudoku.add_cage(3, vec![(1, 1), (1, 2)]).unwrap();
+        sudoku.add_cage(9, vec![(1, 3), (1, 4), (2, 4)]).unwrap();
+        sudoku.check_innies_outies();
+        assert_eq!(candidates(&sudoku, (2, 4)), vec![2]);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{value_char, EdgeKind, LineKind, OutsideKind, Parity, Sudoku};
+use std::fmt::Write;
+
+// cm inside the margins of an A4 page, less room for a heading
+const PAGE_WIDTH: f64 = 18.0;
+const PAGE_HEIGHT: f64 = 24.0;
+
+// printable puzzle sheets: a LaTeX document with columns x rows grids on
+// each page and the solutions at the end, for pdflatex
+#[derive(Debug, Clone)]
+pub struct Booklet {
+    pub title: String,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl Default for Booklet {
+    fn default() -> Booklet {
+        Booklet {
+            title: String::from("Sudoku"),
+            columns: 2,
+            rows: 3,
+        }
+    }
+}
+
+impl Booklet {
+    pub fn write(&self, puzzles: &[Sudoku]) -> String {
+        let mut text = String::new();
+        text += "\\documentclass[a4paper]{article}\n";
+        text += "\\usepackage[margin=1.5cm]{geometry}\n";
+        text += "\\usepackage[T1]{fontenc}\n";
+        text += "\\usepackage{lmodern}\n";
+        text += "\\usepackage{tikz}\n";
+        text += "\\pagestyle{empty}\n";
+        text += "\\setlength{\\parindent}{0pt}\n";
+        text += "\\begin{document}\n";
+        if !self.title.is_empty() {
+            let _ = writeln!(
+                text,
+                "\\begin{{center}}\\LARGE\\textbf{{{}}}\\end{{center}}",
+                escape(&self.title)
+            );
+        }
+        let sheets: Vec<(&Sudoku, Option<Sudoku>)> = puzzles.iter().map(|p| (p, None)).collect();
+        self.pages(&mut text, &sheets, false, self.columns, self.rows);
+        let answers: Vec<(&Sudoku, Option<Sudoku>)> = puzzles
+            .iter()
+            .map(|p| {
+                let mut solved = p.clone();
+                (p, solved.search().then_some(solved))
+            })
+            .collect();
+        text += "\\newpage\n";
+        text += "\\begin{center}\\LARGE\\textbf{Solutions}\\end{center}\n";
+        self.pages(&mut text, &answers, true, self.columns + 1, self.rows + 1);
+        text += "\\end{document}\n";
+        text
+    }
+    // columns x rows slots per page, each grid scaled to fit its slot; an
+    // answer without a solution is labelled instead of drawn
+    fn pages(
+        &self,
+        text: &mut String,
+        sheets: &[(&Sudoku, Option<Sudoku>)],
+        answers: bool,
+        columns: usize,
+        rows: usize,
+    ) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let slot_width = PAGE_WIDTH / columns as f64;
+        let slot_height = PAGE_HEIGHT / rows as f64;
+        for (i, (puzzle, solution)) in sheets.iter().enumerate() {
+            if i > 0 && i % (columns * rows) == 0 {
+                *text += "\\newpage\n";
+            } else if i > 0 && i % columns == 0 {
+                *text += "\\par\\vfill\n";
+            } else if i > 0 {
+                *text += "\\hfill\n";
+            }
+            let extent = puzzle.extent();
+            let cell_cm = ((slot_width - 0.6) / extent).min((slot_height - 1.2) / extent);
+            let _ = writeln!(
+                text,
+                "\\begin{{minipage}}[t]{{{:.2}cm}}\\centering\\textbf{{{}}} {{\\small {}}}\\par\\smallskip",
+                slot_width - 0.3,
+                i + 1,
+                puzzle.rule_notes().join(", ")
+            );
+            match (answers, solution) {
+                (true, None) => *text += "\\textit{no solution}\n",
+                _ => *text += &puzzle.to_tikz(cell_cm, solution.as_ref()),
+            }
+            *text += "\\end{minipage}\n";
+        }
+        *text += "\\par\\vfill\n";
+    }
+}
+
+impl Sudoku {
+    // a tikzpicture of the grid and its markings, cell_cm wide cells; with a
+    // solution every value is filled in, the puzzle's own in bold
+    pub fn to_tikz(&self, cell_cm: f64, solution: Option<&Sudoku>) -> String {
+        let n = self.size;
+        let font = |scale: f64| {
+            let pt = cell_cm * 28.45 * scale;
+            format!("\\fontsize{{{0:.1}}}{{{0:.1}}}\\selectfont ", pt)
+        };
+        let mut t = String::new();
+        let _ = writeln!(
+            t,
+            "\\begin{{tikzpicture}}[x={0:.2}cm,y=-{0:.2}cm,baseline=(current bounding box.north)]",
+            cell_cm
+        );
+        for region in &self.regions {
+            for &(r, c) in region {
+                let _ = writeln!(t, "\\fill[gray!20] ({},{}) rectangle +(1,1);", c - 1, r - 1);
+            }
+        }
+        for &((r, c), parity) in &self.parities {
+            let _ = match parity {
+                Parity::Even => writeln!(
+                    t,
+                    "\\fill[gray!30] ({:.2},{:.2}) rectangle +(0.7,0.7);",
+                    c as f64 - 0.85,
+                    r as f64 - 0.85
+                ),
+                Parity::Odd => writeln!(t, "\\fill[gray!30] {} circle (0.36);", centre((r, c))),
+            };
+        }
+        for line in &self.lines {
+            let path: Vec<String> = line.cells.iter().map(|&p| centre(p)).collect();
+            match line.kind {
+                LineKind::Thermo => {
+                    let _ = writeln!(
+                        t,
+                        "\\draw[gray!40,line width={:.2}cm,line cap=round,line join=round] {};",
+                        cell_cm * 0.25,
+                        path.join(" -- ")
+                    );
+                    let _ = writeln!(t, "\\fill[gray!40] {} circle (0.36);", path[0]);
+                }
+                LineKind::Arrow => {
+                    let _ = writeln!(t, "\\draw[gray!70,thick,-stealth] {};", path.join(" -- "));
+                    let _ = writeln!(
+                        t,
+                        "\\draw[gray!70,thick,fill=white] {} circle (0.38);",
+                        path[0]
+                    );
+                }
+                LineKind::Sandwich(sum) => {
+                    let (r, c) = line.cells[0];
+                    let at = match line.cells.iter().all(|p| p.0 == r) {
+                        true => (r, 0),
+                        false => (0, c),
+                    };
+                    let _ = writeln!(t, "\\node at {} {{{}{}}};", centre(at), font(0.4), sum);
+                }
+            }
+        }
+        if self.diagonal {
+            let _ = writeln!(
+                t,
+                "\\draw[gray!60] (0,0) -- ({0},{0}) ({0},0) -- (0,{0});",
+                n
+            );
+        }
+        let _ = writeln!(
+            t,
+            "\\draw[gray!70,line width=0.3pt] (0,0) grid ({0},{0});",
+            n
+        );
+        let mut thick = String::new();
+        for ((r, c), right) in self.block_sides() {
+            let _ = match right {
+                true => write!(thick, " ({},{}) -- +(0,1)", c, r - 1),
+                false => write!(thick, " ({},{}) -- +(1,0)", c - 1, r),
+            };
+        }
+        if !thick.is_empty() {
+            let _ = writeln!(t, "\\draw[line width=1.2pt]{};", thick);
+        }
+        let _ = writeln!(t, "\\draw[line width=1.6pt] (0,0) rectangle ({0},{0});", n);
+        for cage in &self.cages {
+            let _ = writeln!(
+                t,
+                "\\draw[densely dashed,line width=0.5pt]{};",
+                cage_path(&cage.cells)
+            );
+            if let Some(&(r, c)) = cage.cells.iter().min() {
+                let _ = writeln!(
+                    t,
+                    "\\node[anchor=north west,fill=white,inner sep=0.5pt] at ({:.2},{:.2}) {{{}{}}};",
+                    c as f64 - 0.97,
+                    r as f64 - 0.97,
+                    font(0.22),
+                    cage.sum
+                );
+            }
+        }
+        for edge in &self.edges {
+            let at = format!(
+                "({:.2},{:.2})",
+                (edge.a.1 + edge.b.1) as f64 / 2.0 - 0.5,
+                (edge.a.0 + edge.b.0) as f64 / 2.0 - 0.5
+            );
+            let _ = match edge.kind {
+                EdgeKind::White => writeln!(t, "\\draw[fill=white] {} circle (0.09);", at),
+                EdgeKind::Black => writeln!(t, "\\fill {} circle (0.09);", at),
+                EdgeKind::X | EdgeKind::V => writeln!(
+                    t,
+                    "\\node[fill=white,inner sep=0.5pt] at {} {{{}{}}};",
+                    at,
+                    font(0.3),
+                    match edge.kind {
+                        EdgeKind::X => "X",
+                        _ => "V",
+                    }
+                ),
+                EdgeKind::Less => {
+                    // the sign opens towards the larger digit
+                    let sign = match (edge.a.0 == edge.b.0, edge.a < edge.b) {
+                        (true, true) => "<",
+                        (true, false) => ">",
+                        (false, true) => "\\wedge",
+                        (false, false) => "\\vee",
+                    };
+                    writeln!(
+                        t,
+                        "\\node[fill=white,inner sep=0.5pt] at {} {{{}${}$}};",
+                        at,
+                        font(0.3),
+                        sign
+                    )
+                }
+            };
+        }
+        for clue in &self.outside {
+            let (r, c) = (
+                clue.start.0 as isize - clue.step.0,
+                clue.start.1 as isize - clue.step.1,
+            );
+            let (x, y) = (c as f64 - 0.5, r as f64 - 0.5);
+            let _ = writeln!(
+                t,
+                "\\node at ({:.2},{:.2}) {{{}{}}};",
+                x,
+                y,
+                font(0.4),
+                clue.value
+            );
+            if clue.kind == OutsideKind::LittleKiller {
+                let (dx, dy) = (clue.step.1 as f64, clue.step.0 as f64);
+                let _ = writeln!(
+                    t,
+                    "\\draw[-stealth] ({:.2},{:.2}) -- +({:.2},{:.2});",
+                    x + dx * 0.25,
+                    y + dy * 0.25,
+                    dx * 0.2,
+                    dy * 0.2
+                );
+            }
+        }
+        for cell in &self.data {
+            let pos = (cell.row_id, cell.column_id);
+            let value = match solution {
+                Some(s) => s.data[[pos.0 - 1, pos.1 - 1]].value,
+                None => cell.value,
+            };
+            if value == 0 {
+                continue;
+            }
+            let weight = match solution.is_some() && cell.value != 0 {
+                true => "\\bfseries ",
+                false => "",
+            };
+            let _ = writeln!(
+                t,
+                "\\node at {} {{{}{}{}}};",
+                centre(pos),
+                font(0.6),
+                weight,
+                value_char(value)
+            );
+        }
+        t += "\\end{tikzpicture}\n";
+        t
+    }
+    // grid width in cells, with room for clues outside
+    fn extent(&self) -> f64 {
+        let outside = !self.outside.is_empty()
+            || self
+                .lines
+                .iter()
+                .any(|l| matches!(l.kind, LineKind::Sandwich(_)));
+        match outside {
+            true => self.size as f64 + 2.0,
+            false => self.size as f64,
+        }
+    }
+    // the rules the grid itself does not show
+    fn rule_notes(&self) -> Vec<&'static str> {
+        let mut notes = Vec::new();
+        if self.rules.anti_knight {
+            notes.push("anti-knight");
+        }
+        if self.rules.anti_king {
+            notes.push("anti-king");
+        }
+        if self.rules.non_consecutive {
+            notes.push("non-consecutive");
+        }
+        if self.rules.toroidal {
+            notes.push("toroidal");
+        }
+        if !self.groups.is_empty() {
+            notes.push("extra groups");
+        }
+        if self.kropki_negative {
+            notes.push("all dots given");
+        }
+        if self.xv_negative {
+            notes.push("all X and V given");
+        }
+        notes
+    }
+}
+
+fn centre(pos: (usize, usize)) -> String {
+    format!("({:.1},{:.1})", pos.1 as f64 - 0.5, pos.0 as f64 - 0.5)
+}
+
+// the dashed outline of a cage, drawn a little inside its cells
+fn cage_path(cells: &[(usize, usize)]) -> String {
+    const D: f64 = 0.08;
+    let inside = |r: isize, c: isize| r > 0 && c > 0 && cells.contains(&(r as usize, c as usize));
+    // where a side ends: inset at a convex corner, past the cell at a
+    // concave one so the two sides meet
+    let end = |along: bool, corner: bool| match (along, corner) {
+        (false, _) => D,
+        (true, true) => -D,
+        (true, false) => 0.0,
+    };
+    let mut path = String::new();
+    for &(r, c) in cells {
+        let (ri, ci) = (r as isize, c as isize);
+        let (x, y) = (c as f64 - 1.0, r as f64 - 1.0);
+        // top and bottom sides run from left to right
+        for (dr, yy) in [(-1, y + D), (1, y + 1.0 - D)] {
+            if inside(ri + dr, ci) {
+                continue;
+            }
+            let x0 = x + end(inside(ri, ci - 1), inside(ri + dr, ci - 1));
+            let x1 = x + 1.0 - end(inside(ri, ci + 1), inside(ri + dr, ci + 1));
+            let _ = write!(path, " ({:.2},{:.2}) -- ({:.2},{:.2})", x0, yy, x1, yy);
+        }
+        // left and right sides run from top to bottom
+        for (dc, xx) in [(-1, x + D), (1, x + 1.0 - D)] {
+            if inside(ri, ci + dc) {
+                continue;
+            }
+            let y0 = y + end(inside(ri - 1, ci), inside(ri - 1, ci + dc));
+            let y1 = y + 1.0 - end(inside(ri + 1, ci), inside(ri + 1, ci + dc));
+            let _ = write!(path, " ({:.2},{:.2}) -- ({:.2},{:.2})", xx, y0, xx, y1);
+        }
+    }
+    path
+}
+
+// LaTeX special characters in the title
+fn escape(text: &str) -> String {
+    let mut result = String::new();
+    for ch in text.chars() {
+        match ch {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                result.push('\\');
+                result.push(ch);
+            }
+            '~' => result += "\\textasciitilde{}",
+            '^' => result += "\\textasciicircum{}",
+            '\\' => result += "\\textbackslash{}",
+            _ => result.push(ch),
+        }
+    }
+    result
+}
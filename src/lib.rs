@@ -1,92 +1,165 @@
-use ndarray::{self, s, Array};
+use ndarray::{self, s, Array, ArrayView1};
+use std::fmt;
 
-#[derive(Debug)]
+mod bitboard;
+mod edge;
+mod error;
+mod format;
+mod generator;
+mod house;
+mod image;
+mod killer;
+mod latex;
+mod line;
+mod multi;
+mod outside;
+mod parity;
+mod pencil;
+mod puzzle;
+#[cfg(feature = "serde")]
+mod raw;
+mod rules;
+mod svg;
+mod term;
+mod trace;
+pub use bitboard::Bitboard;
+pub use edge::{Edge, EdgeKind};
+pub use error::SudokuError;
+pub use format::Format;
+pub use house::{Conflict, House};
+pub use killer::Cage;
+pub use latex::Booklet;
+pub use line::{Line, LineKind};
+pub use multi::MultiSudoku;
+pub use outside::{OutsideClue, OutsideKind, Side};
+pub use parity::Parity;
+pub use rules::Rules;
+pub use svg::{Candidate, Highlight};
+pub use trace::Step;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     row_id: usize,
     column_id: usize,
-    value: u8,        // 1~9
-    grid: u32,        // 0b1_1111_1111
-    log: Vec<String>, // log
+    size: usize,    // digits 1~size
+    value: u8,      // 1~size
+    grid: u32,      // 0b1_1111_1111
+    log: Vec<Note>, // log
+    given: bool,    // part of the puzzle, not solved
+}
+// 1~9, then A~P for 10~25
+pub(crate) fn value_char(value: u8) -> char {
+    match value {
+        1..=9 => (b'0' + value) as char,
+        10..=35 => (b'A' + value - 10) as char,
+        _ => '0',
+    }
+}
+// a value within 1~size keeps only its own candidate, if any
+pub(crate) fn cell_fits(value: u8, grid: u32, size: usize) -> bool {
+    match value as usize {
+        0 => grid >> size == 0,
+        v if v <= size => grid & !(1 << (v - 1)) == 0,
+        _ => false,
+    }
+}
+pub(crate) fn char_value(ch: char) -> Option<u8> {
+    match ch {
+        '1'..='9' => Some(ch as u8 - b'0'),
+        'A'..='Z' => Some(ch as u8 - b'A' + 10),
+        'a'..='z' => Some(ch as u8 - b'a' + 10),
+        _ => None,
+    }
+}
+// one entry of a cell's log, turned into text only when it is printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum Note {
+    Initial,
+    Placed(u8),
+    Removed(u8),
+    Erased(u8),
+    LastCandidate((usize, usize), u8),
+    Hidden(House, u8), // the only place left for the value in the house
+    Conflict(u8, (usize, usize)),
+    Recomputed,
+    Text(String), // the reason a variant rule gives
+}
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Note::Initial => write!(f, "Initial..."),
+            Note::Placed(v) => write!(f, "值 {} 被确定!", value_char(*v)),
+            Note::Removed(v) => write!(f, "值 {} 被移除.", value_char(*v)),
+            Note::Erased(v) => write!(f, "值 {} 被擦除.", value_char(*v)),
+            Note::LastCandidate(pos, v) => {
+                write!(
+                    f,
+                    "{}:{} => {}  -- 仅剩一个可能值",
+                    pos.0,
+                    pos.1,
+                    value_char(*v)
+                )
+            }
+            Note::Hidden(house, v) => write!(f, "{}中值{}仅存在于此格", house, value_char(*v)),
+            Note::Conflict(v, p) => write!(f, "值 {} 与 {}:{} 冲突!", value_char(*v), p.0, p.1),
+            Note::Recomputed => write!(f, "可能值已重新计算."),
+            Note::Text(text) => write!(f, "{}", text),
+        }
+    }
 }
 impl Cell {
+    // value or candidates as a mini-grid, then the numbered log
     pub fn print(&self) {
-        println!("--------------------------");
-        println!(
-            "|{}:{}| => |{}{}{}{}{}{}{}{}{}| -- |{}|",
-            self.row_id,
-            self.column_id,
-            match self.check_bit(0) {
-                true => "1",
-                false => "_",
-            },
-            match self.check_bit(1) {
-                true => "2",
-                false => "_",
-            },
-            match self.check_bit(2) {
-                true => "3",
-                false => "_",
-            },
-            match self.check_bit(3) {
-                true => "4",
-                false => "_",
-            },
-            match self.check_bit(4) {
-                true => "5",
-                false => "_",
-            },
-            match self.check_bit(5) {
-                true => "6",
-                false => "_",
-            },
-            match self.check_bit(6) {
-                true => "7",
-                false => "_",
-            },
-            match self.check_bit(7) {
-                true => "8",
-                false => "_",
-            },
-            match self.check_bit(8) {
-                true => "9",
-                false => "_",
-            },
-            self.value
-        );
-        println!("--------------------------");
-        for l in &self.log {
-            println!("{}", l);
+        let state = match (self.value, self.given) {
+            (0, _) => String::from("未填"),
+            (v, true) => format!("值 {} (题目给出)", value_char(v)),
+            (v, false) => format!("值 {} (已解出)", value_char(v)),
+        };
+        println!("── {}:{} {} ──", self.row_id, self.column_id, state);
+        if self.value == 0 {
+            let columns = (1..=self.size).find(|k| k * k >= self.size).unwrap_or(1);
+            for start in (0..self.size).step_by(columns) {
+                let mut line = String::new();
+                for bit in start..(start + columns).min(self.size) {
+                    line.push(' ');
+                    line.push(match self.check_bit(bit) {
+                        true => value_char(bit as u8 + 1),
+                        false => '·',
+                    });
+                }
+                println!("  {}", line);
+            }
         }
-        println!("--------------------------");
+        for (i, l) in self.log.iter().enumerate() {
+            println!("{:4}. {}", i + 1, l);
+        }
+    }
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+    pub fn is_given(&self) -> bool {
+        self.given
     }
     pub fn check_bit(&self, bit: usize) -> bool {
         (self.grid & (1 << bit)) != 0
     }
     pub fn clear_bit(&mut self, bit: usize) {
         if self.check_bit(bit) {
-            self.grid = self.grid & !(1 << bit);
-            self.log.push(format!("值 {} 被移除.", bit + 1));
+            self.grid &= !(1 << bit);
+            self.log.push(Note::Removed(bit as u8 + 1));
         }
     }
     pub fn count(&self) -> usize {
-        let mut c = 0;
-        for i in 0..9 {
-            if self.check_bit(i) {
-                c += 1;
-            }
-        }
-        c
+        self.grid.count_ones() as usize
     }
     pub fn check(&mut self) -> (usize, usize, u8) {
         if self.count() == 1 && self.value == 0 {
-            for i in 0..9u8 {
+            for i in 0..self.size as u8 {
                 if self.check_bit(i.into()) {
-                    self.log.push(format!(
-                        "{}:{} => {}  -- 仅剩一个可能值",
-                        self.row_id,
-                        self.column_id,
-                        i + 1
-                    ));
+                    self.log
+                        .push(Note::LastCandidate((self.row_id, self.column_id), i + 1));
                     return (self.row_id, self.column_id, i + 1);
                 }
             }
@@ -95,7 +168,7 @@ impl Cell {
     }
     pub fn get_values(&self) -> Vec<usize> {
         let mut vs = Vec::new();
-        for i in 0..9 {
+        for i in 0..self.size {
             if self.check_bit(i) {
                 vs.push(i + 1);
             }
@@ -103,68 +176,132 @@ impl Cell {
         vs
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "raw::RawSnapshot"))]
+pub struct Snapshot {
+    cells: Vec<(u8, u32, bool, usize)>, // value, grid, given, log length
+}
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "raw::RawSudoku"))]
 pub struct Sudoku {
+    size: usize,
+    box_rows: usize,
+    box_columns: usize,
+    diagonal: bool,
+    regions: Vec<Vec<(usize, usize)>>,
+    groups: Vec<Vec<(usize, usize)>>, // no repeats, fewer cells than digits allowed
+    jigsaw: Option<Vec<usize>>,       // block number of each cell, row by row
+    cages: Vec<Cage>,
+    edges: Vec<Edge>,
+    rules: Rules,
+    kropki_negative: bool,
+    xv_negative: bool,
+    lines: Vec<Line>,
+    parities: Vec<((usize, usize), Parity)>,
+    outside: Vec<OutsideClue>,
     data: ndarray::Array2<Cell>,
 }
+impl Default for Sudoku {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.data {
+            write!(f, "{}", value_char(c.value))?;
+        }
+        Ok(())
+    }
+}
 impl Sudoku {
     pub fn new() -> Sudoku {
+        Self::build(3, 3)
+    }
+    // box_rows x box_columns boxes on a (box_rows * box_columns) square grid, e.g. 2x3 for 6x6
+    pub fn with_size(box_rows: usize, box_columns: usize) -> Result<Sudoku, SudokuError> {
+        let size = box_rows * box_columns;
+        if size == 0 || size > 25 {
+            return Err(SudokuError::InvalidSize(box_rows, box_columns));
+        }
+        Ok(Self::build(box_rows, box_columns))
+    }
+    fn build(box_rows: usize, box_columns: usize) -> Sudoku {
+        let size = box_rows * box_columns;
         let mut v = Vec::new();
-        for i in 1..=81 {
+        for i in 1..=size * size {
             v.push(Cell {
-                row_id: (i - 1) / 9 + 1,
-                column_id: (i - 1) % 9 + 1,
+                row_id: (i - 1) / size + 1,
+                column_id: (i - 1) % size + 1,
+                size,
                 value: 0,
-                grid: 0b1_1111_1111,
-                log: vec![String::from("Initial...")],
+                grid: (1 << size) - 1,
+                log: vec![Note::Initial],
+                given: false,
             });
         }
-        let data = Array::from_shape_vec((9, 9), v).unwrap();
-        //let data = Array::random((9, 9), Uniform::new(1, 10)); //Array::zeros((9, 9));
-        Sudoku { data }
+        let data = Array::from_shape_vec((size, size), v).unwrap();
+        Sudoku {
+            size,
+            box_rows,
+            box_columns,
+            diagonal: false,
+            regions: Vec::new(),
+            groups: Vec::new(),
+            jigsaw: None,
+            cages: Vec::new(),
+            edges: Vec::new(),
+            rules: Rules::default(),
+            kropki_negative: false,
+            xv_negative: false,
+            lines: Vec::new(),
+            parities: Vec::new(),
+            outside: Vec::new(),
+            data,
+        }
+    }
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    pub fn box_shape(&self) -> (usize, usize) {
+        (self.box_rows, self.box_columns)
+    }
+    fn full_grid(&self) -> u32 {
+        (1 << self.size) - 1
     }
     pub fn clean(&mut self) {
+        let grid = self.full_grid();
         for c in &mut self.data {
             c.value = 0;
-            c.grid = 0b1_1111_1111;
-            c.log = vec![String::from("Initial...")];
+            c.grid = grid;
+            c.log.clear();
+            c.log.push(Note::Initial);
+            c.given = false;
         }
+        self.apply_parities();
     }
     pub fn init(&mut self, str: &str) {
-        let mut index = 0;
-        for i in str.chars() {
-            match i {
-                //'0' => self.set_value((index / 9 + 1, index % 9 + 1), 0),
-                '1' => self.set_value((index / 9 + 1, index % 9 + 1), 1),
-                '2' => self.set_value((index / 9 + 1, index % 9 + 1), 2),
-                '3' => self.set_value((index / 9 + 1, index % 9 + 1), 3),
-                '4' => self.set_value((index / 9 + 1, index % 9 + 1), 4),
-                '5' => self.set_value((index / 9 + 1, index % 9 + 1), 5),
-                '6' => self.set_value((index / 9 + 1, index % 9 + 1), 6),
-                '7' => self.set_value((index / 9 + 1, index % 9 + 1), 7),
-                '8' => self.set_value((index / 9 + 1, index % 9 + 1), 8),
-                '9' => self.set_value((index / 9 + 1, index % 9 + 1), 9),
-                _ => (),
+        let size = self.size;
+        for (index, i) in str.chars().enumerate() {
+            if index >= size * size {
+                break;
+            }
+            if let Some(value) = char_value(i) {
+                self.set_value((index / size + 1, index % size + 1), value);
             }
-            index = index + 1;
         }
+        self.mark_givens();
     }
-    pub fn print(&self) {
-        println!(" -------------------");
-        for i in 1..=9 {
-            let row = self.get_row(i);
-            let mut display = String::new();
-            for i in 0..9 {
-                if row[i].value == 0 {
-                    display += " ";
-                } else {
-                    display += row[i].value.to_string().as_str();
-                }
-                display += " ";
-            }
-            println!("| {}|", display);
+    // the values placed so far become the puzzle's givens
+    pub(crate) fn mark_givens(&mut self) {
+        for c in &mut self.data {
+            c.given = c.value != 0;
         }
-        println!(" -------------------");
+    }
+    pub fn print(&self) {
+        self.print_highlight(&Highlight::default());
     }
     pub fn print_cell(&self, r: usize, c: usize) {
         self.data[[r - 1, c - 1]].print();
@@ -178,66 +315,241 @@ impl Sudoku {
         }
         count
     }
-    pub fn get_row(&self, row: usize) -> Vec<&Cell> {
-        let result: Vec<&Cell> = self.data.row(row - 1).into_iter().collect();
-        result
+    // views into the board, nothing is copied
+    pub fn get_row(&self, row: usize) -> ArrayView1<'_, Cell> {
+        self.data.row(row - 1)
     }
-    pub fn get_column(&self, column: usize) -> Vec<&Cell> {
-        let result: Vec<&Cell> = self.data.column(column - 1).into_iter().collect();
-        result
+    pub fn get_column(&self, column: usize) -> ArrayView1<'_, Cell> {
+        self.data.column(column - 1)
     }
-    pub fn get_block(&self, block: usize) -> Vec<&Cell> {
-        let sliceblock = match block {
-            1 => self.data.slice(s![0..3, 0..3]),
-            2 => self.data.slice(s![0..3, 3..6]),
-            3 => self.data.slice(s![0..3, 6..9]),
-            4 => self.data.slice(s![3..6, 0..3]),
-            5 => self.data.slice(s![3..6, 3..6]),
-            6 => self.data.slice(s![3..6, 6..9]),
-            7 => self.data.slice(s![6..9, 0..3]),
-            8 => self.data.slice(s![6..9, 3..6]),
-            9 => self.data.slice(s![6..9, 6..9]),
-            _ => self.data.slice(s![0..3, 0..3]),
+    // the cells of a block row by row; a jigsaw block is looked up in the
+    // whole board, a regular one only in its own rows and columns
+    pub fn get_block(&self, block: usize) -> impl Iterator<Item = &Cell> + '_ {
+        let block = match (1..=self.size).contains(&block) {
+            true => block,
+            false => 1,
         };
-        let result: Vec<&Cell> = sliceblock.into_iter().collect();
-        result
+        let per_band = self.size / self.box_columns;
+        let r = ((block - 1) / per_band) * self.box_rows;
+        let c = ((block - 1) % per_band) * self.box_columns;
+        let (rows, columns) = match self.jigsaw {
+            Some(_) => (0..self.size, 0..self.size),
+            None => (r..r + self.box_rows, c..c + self.box_columns),
+        };
+        self.data
+            .slice(s![rows, columns])
+            .into_iter()
+            .filter(move |c| self.get_block_index((c.row_id, c.column_id)) == block)
     }
     pub fn set_value(&mut self, pos: (usize, usize), value: u8) {
+        if value == 0 || value as usize > self.size {
+            return;
+        }
         self.data[[pos.0 - 1, pos.1 - 1]].value = value;
         self.data[[pos.0 - 1, pos.1 - 1]]
             .log
-            .push(format!("值 {} 被确定!", value));
-        self.data[[pos.0 - 1, pos.1 - 1]].grid = match value {
-            1..=9 => ndarray_rand::rand_distr::num_traits::Pow::pow(2u32, value - 1),
-            _ => self.data[[pos.0 - 1, pos.1 - 1]].grid,
-        };
+            .push(Note::Placed(value));
+        self.data[[pos.0 - 1, pos.1 - 1]].grid = 1 << (value - 1);
         let mut ps: Vec<(usize, usize)> = Vec::new();
+        let mut conflicts: Vec<(usize, usize)> = Vec::new();
         for c in self.get_3vec(pos) {
             if c.value == 0 {
                 ps.push((c.row_id, c.column_id));
+            } else if c.value == value && (c.row_id, c.column_id) != pos {
+                conflicts.push((c.row_id, c.column_id));
             }
         }
+        for p in conflicts {
+            // no candidate left, so is_error() reports the contradiction right away
+            self.data[[pos.0 - 1, pos.1 - 1]].grid = 0;
+            self.data[[pos.0 - 1, pos.1 - 1]]
+                .log
+                .push(Note::Conflict(value, p));
+        }
         for p in ps {
             self.data[[p.0 - 1, p.1 - 1]].clear_bit((value - 1).into());
         }
     }
+    pub fn try_init(&mut self, str: &str) -> Result<(), SudokuError> {
+        let size = self.size;
+        let chars: Vec<char> = str.chars().filter(|ch| !ch.is_whitespace()).collect();
+        if chars.len() != size * size {
+            return Err(SudokuError::InvalidLength(chars.len(), size * size));
+        }
+        let mut values = Vec::new();
+        for (index, &ch) in chars.iter().enumerate() {
+            match char_value(ch) {
+                Some(value) if value as usize <= size => values.push((index, value)),
+                _ if ch == '0' || ch == '.' => (),
+                _ => return Err(SudokuError::InvalidDigit(index + 1, ch)),
+            }
+        }
+        let store = self.snapshot();
+        for (index, value) in values {
+            if let Err(e) = self.try_set_value((index / size + 1, index % size + 1), value) {
+                self.restore(&store);
+                return Err(e);
+            }
+        }
+        self.mark_givens();
+        Ok(())
+    }
+    pub fn try_set_value(&mut self, pos: (usize, usize), value: u8) -> Result<(), SudokuError> {
+        let cell = self.try_get_cell(pos)?;
+        if value == 0 || value as usize > self.size {
+            return Err(SudokuError::InvalidValue(value));
+        }
+        if cell.value == value {
+            return Ok(());
+        }
+        if cell.value != 0 {
+            return Err(SudokuError::CellFilled(pos, cell.value));
+        }
+        if !self.parity(pos).is_none_or(|p| p.allows(value)) {
+            return Err(SudokuError::ParityViolation(pos, value));
+        }
+        for c in self.get_3vec(pos) {
+            if c.value == value && (c.row_id, c.column_id) != pos {
+                return Err(SudokuError::Conflict {
+                    pos,
+                    value,
+                    with: (c.row_id, c.column_id),
+                });
+            }
+        }
+        self.set_value(pos, value);
+        Ok(())
+    }
+    pub fn try_get_cell(&self, pos: (usize, usize)) -> Result<&Cell, SudokuError> {
+        if !(1..=self.size).contains(&pos.0) || !(1..=self.size).contains(&pos.1) {
+            return Err(SudokuError::InvalidPosition(pos.0, pos.1));
+        }
+        Ok(&self.data[[pos.0 - 1, pos.1 - 1]])
+    }
+    pub fn try_get_row(&self, row: usize) -> Result<ArrayView1<'_, Cell>, SudokuError> {
+        if !(1..=self.size).contains(&row) {
+            return Err(SudokuError::InvalidIndex(row));
+        }
+        Ok(self.get_row(row))
+    }
+    pub fn try_get_column(&self, column: usize) -> Result<ArrayView1<'_, Cell>, SudokuError> {
+        if !(1..=self.size).contains(&column) {
+            return Err(SudokuError::InvalidIndex(column));
+        }
+        Ok(self.get_column(column))
+    }
+    pub fn try_get_block(
+        &self,
+        block: usize,
+    ) -> Result<impl Iterator<Item = &Cell> + '_, SudokuError> {
+        if !(1..=self.size).contains(&block) {
+            return Err(SudokuError::InvalidIndex(block));
+        }
+        Ok(self.get_block(block))
+    }
+    pub fn try_get_3vec(&self, pos: (usize, usize)) -> Result<Vec<&Cell>, SudokuError> {
+        self.try_get_cell(pos)?;
+        Ok(self.get_3vec(pos))
+    }
     pub fn clear_value(&mut self, pos: (usize, usize), value: u8) {
-        let _ = self.data[[pos.0 - 1, pos.1 - 1]].grid | !(1 << value);
+        if (1..=self.size).contains(&(value as usize))
+            && self.data[[pos.0 - 1, pos.1 - 1]].value == 0
+        {
+            self.data[[pos.0 - 1, pos.1 - 1]].clear_bit((value - 1).into());
+        }
+    }
+    pub fn try_clear_value(&mut self, pos: (usize, usize), value: u8) -> Result<(), SudokuError> {
+        let cell = self.try_get_cell(pos)?;
+        if value == 0 || value as usize > self.size {
+            return Err(SudokuError::InvalidValue(value));
+        }
+        if cell.value != 0 {
+            return Err(SudokuError::CellFilled(pos, cell.value));
+        }
+        self.clear_value(pos, value);
+        Ok(())
     }
+    // erases givens too, e.g. when editing the puzzle; try_erase_value keeps them
+    pub fn erase_value(&mut self, pos: (usize, usize)) {
+        let value = self.data[[pos.0 - 1, pos.1 - 1]].value;
+        if value == 0 {
+            return;
+        }
+        self.data[[pos.0 - 1, pos.1 - 1]].value = 0;
+        self.data[[pos.0 - 1, pos.1 - 1]].given = false;
+        self.data[[pos.0 - 1, pos.1 - 1]]
+            .log
+            .push(Note::Erased(value));
+        let mut ps: Vec<(usize, usize)> = vec![pos];
+        for c in self.get_3vec(pos) {
+            if c.value == 0 && !ps.contains(&(c.row_id, c.column_id)) {
+                ps.push((c.row_id, c.column_id));
+            }
+        }
+        for p in ps {
+            self.recompute_grid(p);
+        }
+    }
+    pub fn try_erase_value(&mut self, pos: (usize, usize)) -> Result<(), SudokuError> {
+        let cell = self.try_get_cell(pos)?;
+        if cell.given {
+            return Err(SudokuError::GivenCell(pos, cell.value));
+        }
+        self.erase_value(pos);
+        Ok(())
+    }
+    // candidates of an empty cell from the values currently placed in its peers
+    fn recompute_grid(&mut self, pos: (usize, usize)) {
+        let mut grid = self.parity_mask(pos);
+        for c in self.get_3vec(pos) {
+            if c.value != 0 && (c.row_id, c.column_id) != pos {
+                grid &= !(1 << (c.value - 1));
+            }
+        }
+        let cell = &mut self.data[[pos.0 - 1, pos.1 - 1]];
+        if cell.grid != grid {
+            cell.grid = grid;
+            cell.log.push(Note::Recomputed);
+        }
+    }
+    // the peers of pos as computed by the rules, see Sudoku::peers
     pub fn get_3vec(&self, pos: (usize, usize)) -> Vec<&Cell> {
-        let v1 = self.get_row(pos.0);
-        let v2 = self.get_column(pos.1);
-        let v3 = self.get_block(((pos.0 - 1) / 3) * 3 + (pos.1 - 1) / 3 + 1);
-        let mut v: Vec<&Cell> = Vec::new();
-        v.extend(v1.iter());
-        v.extend(v2.iter());
-        v.extend(v3.iter());
-        v
+        self.peers(pos)
+            .into_iter()
+            .map(|(r, c)| &self.data[[r - 1, c - 1]])
+            .collect()
+    }
+    // rebuild the candidates of every empty cell from the placed values,
+    // used when a rule or house is removed and eliminations may be stale
+    pub(crate) fn recompute_grids(&mut self) {
+        for r in 1..=self.size {
+            for c in 1..=self.size {
+                if self.data[[r - 1, c - 1]].value == 0 {
+                    self.recompute_grid((r, c));
+                }
+            }
+        }
+    }
+    // clear every placed value from its empty peers, used after a house is added
+    fn apply_peers(&mut self) {
+        let mut ps = Vec::new();
+        for c in &self.data {
+            if c.value != 0 {
+                for p in self.get_3vec((c.row_id, c.column_id)) {
+                    if p.value == 0 {
+                        ps.push(((p.row_id, p.column_id), c.value));
+                    }
+                }
+            }
+        }
+        for (p, v) in ps {
+            self.data[[p.0 - 1, p.1 - 1]].clear_bit((v - 1).into());
+        }
     }
     pub fn get_empty(&self) -> Vec<(usize, usize, usize)> {
         let mut result = Vec::new();
-        for i in 1..10 {
-            for j in 1..10 {
+        for i in 1..=self.size {
+            for j in 1..=self.size {
                 if self.data[[i - 1, j - 1]].value == 0 {
                     result.push((i, j, self.get_block_index((i, j))));
                 }
@@ -245,43 +557,30 @@ impl Sudoku {
         }
         result
     }
-    pub fn is_unique_element(&self, v: &Vec<(usize, usize, usize)>, x: usize) -> bool {
+    pub fn is_unique_element(&self, v: &[(usize, usize, usize)], x: usize) -> bool {
         let mut count = 0;
         for &i in v.iter() {
             if i.2 == x {
                 count += 1;
             }
         }
-        return count == 1;
+        count == 1
     }
     pub fn get_block_index(&self, pos: (usize, usize)) -> usize {
-        match pos.0 {
-            1 | 2 | 3 => match pos.1 {
-                1 | 2 | 3 => 1,
-                4 | 5 | 6 => 2,
-                7 | 8 | 9 => 3,
-                _ => 0,
-            },
-            4 | 5 | 6 => match pos.1 {
-                1 | 2 | 3 => 4,
-                4 | 5 | 6 => 5,
-                7 | 8 | 9 => 6,
-                _ => 0,
-            },
-            7 | 8 | 9 => match pos.1 {
-                1 | 2 | 3 => 7,
-                4 | 5 | 6 => 8,
-                7 | 8 | 9 => 9,
-                _ => 0,
-            },
-            _ => 0,
+        if !(1..=self.size).contains(&pos.0) || !(1..=self.size).contains(&pos.1) {
+            return 0;
+        }
+        if let Some(map) = &self.jigsaw {
+            return map[(pos.0 - 1) * self.size + pos.1 - 1];
         }
+        let per_band = self.size / self.box_columns;
+        ((pos.0 - 1) / self.box_rows) * per_band + (pos.1 - 1) / self.box_columns + 1
     }
     pub fn remaining_count(&self) -> (usize, usize) {
         let mut result = 0;
         let mut totalvalues = 0;
-        for i in 0..9 {
-            for j in 0..9 {
+        for i in 0..self.size {
+            for j in 0..self.size {
                 totalvalues += self.data[[i, j]].count();
                 if self.data[[i, j]].value == 0 {
                     result += 1;
@@ -302,101 +601,72 @@ impl Sudoku {
             self.set_value((p.0, p.1), p.2);
         }
     }
-    pub fn check_row(&mut self) {
-        let mut result = Vec::new();
-        for rowid in 1..=9 {
-            let row = self.get_row(rowid);
-            for i in 0..9u8 {
-                let mut count = 0;
-                let mut pos = (0, 0);
-                for c in &row {
-                    if c.value == 0 {
-                        if c.check_bit(i.into()) {
-                            pos = (c.row_id, c.column_id);
-                            count += 1;
-                        }
-                    }
-                }
-                if count == 1 {
-                    result.push((pos, i));
+    // digits with a single place left among cells, as (position, bit)
+    fn hidden_singles<'a>(
+        size: usize,
+        cells: impl Iterator<Item = &'a Cell>,
+        result: &mut Vec<((usize, usize), u8)>,
+    ) {
+        let mut count = [0; 25];
+        let mut pos = [(0, 0); 25];
+        for c in cells.filter(|c| c.value == 0) {
+            for bit in 0..size {
+                if c.check_bit(bit) {
+                    count[bit] += 1;
+                    pos[bit] = (c.row_id, c.column_id);
                 }
             }
         }
+        for (bit, (&n, &p)) in count.iter().zip(&pos).take(size).enumerate() {
+            if n == 1 {
+                result.push((p, bit as u8));
+            }
+        }
+    }
+    pub fn check_row(&mut self) {
+        let mut result = Vec::new();
+        for rowid in 1..=self.size {
+            Self::hidden_singles(self.size, self.get_row(rowid).into_iter(), &mut result);
+        }
         for r in result {
-            self.data[[r.0 .0 - 1, r.0 .1 - 1]].log.push(format!(
-                "行{}中值{}仅存在于此格",
-                r.0 .0,
-                r.1 + 1,
-            ));
+            self.data[[r.0 .0 - 1, r.0 .1 - 1]]
+                .log
+                .push(Note::Hidden(House::Row(r.0 .0), r.1 + 1));
             self.set_value(r.0, r.1 + 1);
         }
     }
     pub fn check_column(&mut self) {
         let mut result = Vec::new();
-        for rowid in 1..=9 {
-            let row = self.get_column(rowid);
-            for i in 0..9u8 {
-                let mut count = 0;
-                let mut pos = (0, 0);
-                for c in &row {
-                    if c.value == 0 {
-                        if c.check_bit(i.into()) {
-                            pos = (c.row_id, c.column_id);
-                            count += 1;
-                        }
-                    }
-                }
-                if count == 1 {
-                    result.push((pos, i));
-                }
-            }
+        for rowid in 1..=self.size {
+            Self::hidden_singles(self.size, self.get_column(rowid).into_iter(), &mut result);
         }
         for r in result {
-            self.data[[r.0 .0 - 1, r.0 .1 - 1]].log.push(format!(
-                "列{}中值{}仅存在于此格",
-                r.0 .1,
-                r.1 + 1
-            ));
+            self.data[[r.0 .0 - 1, r.0 .1 - 1]]
+                .log
+                .push(Note::Hidden(House::Column(r.0 .1), r.1 + 1));
             self.set_value(r.0, r.1 + 1);
         }
     }
     pub fn check_block(&mut self) {
         let mut result = Vec::new();
-        for rowid in 1..=9 {
-            let row = self.get_block(rowid);
-            for i in 0..9u8 {
-                let mut count = 0;
-                let mut pos = (0, 0);
-                for c in &row {
-                    if c.value == 0 {
-                        if c.check_bit(i.into()) {
-                            pos = (c.row_id, c.column_id);
-                            count += 1;
-                        }
-                    }
-                }
-                if count == 1 {
-                    result.push((pos, i));
-                }
-            }
+        for rowid in 1..=self.size {
+            Self::hidden_singles(self.size, self.get_block(rowid), &mut result);
         }
         for r in result {
-            self.data[[r.0 .0 - 1, r.0 .1 - 1]].log.push(format!(
-                "{}:{}块中值{}仅存在于此格",
-                r.0 .0,
-                r.0 .1,
-                r.1 + 1
-            ));
+            let block = self.get_block_index(r.0);
+            self.data[[r.0 .0 - 1, r.0 .1 - 1]]
+                .log
+                .push(Note::Hidden(House::Block(block), r.1 + 1));
             self.set_value(r.0, r.1 + 1);
         }
     }
     pub fn check_2and2(&mut self) {
-        for i in 1..=9 {
+        for i in 1..=self.size {
             let row = self.get_row(i);
             let mut vs: Vec<usize> = Vec::new();
-            for index in 0..9 {
+            for index in 0..self.size {
                 if row[index].count() == 2 {
-                    for _index in index..9 {
+                    for _index in index..self.size {
                         if _index == index {
                             continue;
                         }
@@ -407,9 +677,9 @@ impl Sudoku {
                     }
                 }
             }
-            if vs.len() > 0 {
+            if !vs.is_empty() {
                 for v in &vs {
-                    for j in 0..9 {
+                    for j in 0..self.size {
                         if self.data[[i - 1, j]].get_values() != vs {
                             self.data[[i - 1, j]].clear_bit(*v - 1);
                             //self.data[[i - 1, j]].print();
@@ -418,12 +688,12 @@ impl Sudoku {
                 }
             }
         }
-        for i in 1..=9 {
+        for i in 1..=self.size {
             let row = self.get_column(i);
             let mut vs: Vec<usize> = Vec::new();
-            for index in 0..9 {
+            for index in 0..self.size {
                 if row[index].count() == 2 {
-                    for _index in index..9 {
+                    for _index in index..self.size {
                         if _index == index {
                             continue;
                         }
@@ -434,9 +704,9 @@ impl Sudoku {
                     }
                 }
             }
-            if vs.len() > 0 {
+            if !vs.is_empty() {
                 for v in &vs {
-                    for j in 0..9 {
+                    for j in 0..self.size {
                         if self.data[[j, i - 1]].get_values() != vs {
                             self.data[[j, i - 1]].clear_bit(*v - 1);
                         }
@@ -444,28 +714,19 @@ impl Sudoku {
                 }
             }
         }
-        for i in 1..=9 {
-            let row = self.get_block(i);
+        for i in 1..=self.size {
             let mut vs: Vec<usize> = Vec::new();
-            for index in 0..9 {
-                if row[index].count() == 2 {
-                    for _index in index..9 {
-                        if _index == index {
-                            continue;
-                        }
-                        if row[index].grid == row[_index].grid {
-                            //println!("{}:{} => B2x2 <= {}:{}", i, index + 1, i, _index + 1);
-                            vs = row[index].get_values();
-                        }
-                    }
+            for (index, a) in self.get_block(i).enumerate() {
+                if a.count() == 2 && self.get_block(i).skip(index + 1).any(|b| a.grid == b.grid) {
+                    vs = a.get_values();
                 }
             }
-            if vs.len() > 0 {
+            if !vs.is_empty() {
                 let mut pos: Vec<(usize, usize, usize)> = Vec::new();
                 for v in &vs {
-                    for j in 0..9 {
-                        if row[j].get_values() != vs {
-                            pos.push((row[j].row_id, row[j].column_id, v - 1));
+                    for c in self.get_block(i) {
+                        if c.get_values() != vs {
+                            pos.push((c.row_id, c.column_id, v - 1));
                         }
                     }
                 }
@@ -477,124 +738,92 @@ impl Sudoku {
     }
     pub fn check_row_column_in_block(&mut self) {
         let mut result = Vec::new();
-        for block_id in 1..=9 {
-            let block = self.get_block(block_id);
-            for i in 1..=9 {
-                let mut i_count = 0;
-                for c in &block {
-                    if c.check_bit(i - 1) {
-                        i_count += 1;
+        for block_id in 1..=self.size {
+            for i in 1..=self.size {
+                // the row and column shared by every cell with i, 0 once they differ
+                let (mut count, mut row, mut column) = (0, 0, 0);
+                for c in self.get_block(block_id).filter(|c| c.check_bit(i - 1)) {
+                    if count == 0 {
+                        (row, column) = (c.row_id, c.column_id);
                     }
-                }
-                if i_count == 1 {
-                    continue;
-                }
-                // check row
-                let mut i_row = 0;
-                let mut row_id = 0;
-                for c in &block[1..3] {
-                    row_id = c.row_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
+                    if c.row_id != row {
+                        row = 0;
                     }
-                }
-                if i_count == i_row {
-                    result.push((i, block_id, row_id, 0));
-                }
-                i_row = 0;
-                for c in &block[4..6] {
-                    row_id = c.row_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
+                    if c.column_id != column {
+                        column = 0;
                     }
+                    count += 1;
                 }
-                if i_count == i_row {
-                    result.push((i, block_id, row_id, 0));
-                }
-                i_row = 0;
-                for c in &block[7..9] {
-                    row_id = c.row_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
-                    }
+                if count < 2 {
+                    continue;
                 }
-                if i_count == i_row {
-                    result.push((i, block_id, row_id, 0));
+                // check row
+                if row != 0 {
+                    result.push((i, block_id, row, 0));
                 }
                 // check column
-                let mut i_row = 0;
-                let mut row_id = 0;
-                for c in vec![block[0], block[3], block[6]] {
-                    row_id = c.column_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
-                    }
+                if column != 0 {
+                    result.push((i, block_id, 0, column));
                 }
-                if i_count == i_row {
-                    result.push((i, block_id, 0, row_id));
+            }
+        }
+        for res in result {
+            for j in 1..=self.size {
+                let pos = match res.2 {
+                    0 => (j, res.3),
+                    _ => (res.2, j),
+                };
+                if self.get_block_index(pos) != res.1 {
+                    self.data[[pos.0 - 1, pos.1 - 1]].clear_bit(res.0 - 1);
                 }
-                i_row = 0;
-                for c in vec![block[1], block[4], block[7]] {
-                    row_id = c.column_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
+            }
+        }
+        // a row or column whose candidates for a value all sit in one block
+        // clears that value from the rest of the block, whatever its shape
+        let mut result = Vec::new();
+        for line in 1..=self.size {
+            for (line_cells, is_row) in [(self.get_row(line), true), (self.get_column(line), false)]
+            {
+                for i in 1..=self.size {
+                    // the block shared by every cell with i, 0 once they differ
+                    let (mut count, mut block_id) = (0, 0);
+                    for c in line_cells.iter().filter(|c| c.check_bit(i - 1)) {
+                        let b = self.get_block_index((c.row_id, c.column_id));
+                        if count == 0 {
+                            block_id = b;
+                        } else if b != block_id {
+                            block_id = 0;
+                        }
+                        count += 1;
                     }
-                }
-                if i_count == i_row {
-                    result.push((i, block_id, 0, row_id));
-                }
-                i_row = 0;
-                for c in vec![block[2], block[5], block[8]] {
-                    row_id = c.column_id;
-                    if c.check_bit(i - 1) {
-                        i_row += 1;
+                    if count >= 2 && block_id != 0 {
+                        result.push((i, block_id, line, is_row));
                     }
                 }
-                if i_count == i_row {
-                    result.push((i, block_id, 0, row_id));
-                }
             }
         }
-        //println!("result : {:?}", result);
-        for res in result {
-            if res.2 == 0 {
-                let mut row_vec = Vec::new();
-                match res.1 {
-                    1 | 2 | 3 => row_vec = vec![4, 5, 6, 7, 8, 9],
-                    4 | 5 | 6 => row_vec = vec![1, 2, 3, 7, 8, 9],
-                    7 | 8 | 9 => row_vec = vec![1, 2, 3, 4, 5, 6],
-                    _ => (),
-                }
-                //println!("{:?}  : {}", row_vec, self.remaining_count().1) ;
-                for r in row_vec {
-                    //println!("{}:{} >> {}", r , res.3 , res.0);
-                    self.data[[r - 1, res.3 - 1]].clear_bit(res.0 - 1);
-                }
-            } else {
-                let mut column_vec = Vec::new();
-                match res.1 {
-                    1 | 4 | 7 => column_vec = vec![4, 5, 6, 7, 8, 9],
-                    2 | 5 | 8 => column_vec = vec![1, 2, 3, 7, 8, 9],
-                    3 | 6 | 9 => column_vec = vec![1, 2, 3, 4, 5, 6],
-                    _ => (),
-                }
-                //println!("{:?}   : {}", column_vec, self.remaining_count().1) ;
-                for c in column_vec {
-                    //println!("{}:{} >> {}", res.2 , c , res.0);
-                    self.data[[res.2 - 1, c - 1]].clear_bit(res.0 - 1);
+        for (i, block_id, line, is_row) in result {
+            let mut ps = Vec::new();
+            for c in self.get_block(block_id) {
+                let on_line = match is_row {
+                    true => c.row_id == line,
+                    false => c.column_id == line,
+                };
+                if !on_line {
+                    ps.push((c.row_id, c.column_id));
                 }
             }
+            for p in ps {
+                self.data[[p.0 - 1, p.1 - 1]].clear_bit(i - 1);
+            }
         }
     }
     pub fn calculate(&mut self) {
         loop {
             let count = self.remaining_count();
-            self.check_all_cells();
-            self.check_row();
-            self.check_column();
-            self.check_block();
-            self.check_2and2();
-            self.check_row_column_in_block();
+            for (_, technique) in trace::TECHNIQUES {
+                technique(self);
+            }
             if count == self.remaining_count() {
                 break;
             }
@@ -604,7 +833,7 @@ impl Sudoku {
     pub fn exam(&self) -> bool {
         let remain = self.remaining_count();
         if remain.0 == 0 {
-            if remain.1 == 81 {
+            if self.is_solved() {
                 println!("**** DONE! {:?}", remain);
                 return true;
             } else {
@@ -613,16 +842,51 @@ impl Sudoku {
         } else {
             println!("**** Remain {:?} ", remain);
         }
-        return false;
+        false
     }
-    pub fn to_string(&self) -> String {
-        let mut data = String::new();
+    pub fn to_bitboard(&self) -> Result<Bitboard, SudokuError> {
+        if !self.fits_bitboard() {
+            return Err(SudokuError::Unsupported);
+        }
+        let mut board = match &self.jigsaw {
+            Some(map) => Bitboard::with_blocks(map),
+            None => Bitboard::new(),
+        };
         for c in &self.data {
-            data.push_str(&c.value.to_string());
+            let index = (c.row_id - 1) * 9 + c.column_id - 1;
+            if c.value != 0 {
+                if !board.place(index, c.value) {
+                    board.restrict(index, 0);
+                }
+            } else {
+                board.restrict(index, c.grid as u16);
+            }
         }
-        data.push('\r');
-        data.push('\n');
-        data
+        Ok(board)
+    }
+    pub fn load_bitboard(&mut self, board: &Bitboard) -> Result<(), SudokuError> {
+        if self.size != 9 {
+            return Err(SudokuError::InvalidSize(self.box_rows, self.box_columns));
+        }
+        for index in 0..81 {
+            let pos = (index / 9 + 1, index % 9 + 1);
+            let value = board.value(index);
+            if value != 0 && self.data[[pos.0 - 1, pos.1 - 1]].value == 0 {
+                self.set_value(pos, value);
+            }
+        }
+        Ok(())
+    }
+    // falls back to search() for anything but a plain 9x9 grid
+    pub fn solve_fast(&mut self) -> bool {
+        let mut board = match self.to_bitboard() {
+            Ok(board) => board,
+            Err(_) => return self.search(),
+        };
+        if board.solve() {
+            return self.load_bitboard(&board).is_ok();
+        }
+        false
     }
     pub fn is_error(&self) -> bool {
         for c in &self.data {
@@ -630,7 +894,89 @@ impl Sudoku {
                 return true;
             }
         }
-        return false;
+        false
+    }
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self
+                .data
+                .iter()
+                .map(|c| (c.value, c.grid, c.given, c.log.len()))
+                .collect(),
+        }
+    }
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        for (c, &(value, grid, given, log_len)) in self.data.iter_mut().zip(&snapshot.cells) {
+            c.value = value;
+            c.grid = grid;
+            c.given = given;
+            c.log.truncate(log_len);
+        }
+    }
+    // restore a snapshot that may come from elsewhere, e.g. a saved file
+    pub fn try_restore(&mut self, snapshot: &Snapshot) -> Result<(), SudokuError> {
+        self.check_snapshot(snapshot)?;
+        self.restore(snapshot);
+        Ok(())
+    }
+    pub(crate) fn check_snapshot(&self, snapshot: &Snapshot) -> Result<(), SudokuError> {
+        let n = self.size * self.size;
+        if snapshot.cells.len() != n {
+            return Err(SudokuError::InvalidLength(snapshot.cells.len(), n));
+        }
+        for &(value, grid, given, _) in &snapshot.cells {
+            if !cell_fits(value, grid, self.size) || (given && value == 0) {
+                return Err(SudokuError::InvalidValue(value));
+            }
+        }
+        Ok(())
+    }
+    pub fn is_solved(&self) -> bool {
+        self.remaining_count() == (0, self.size * self.size)
+            && self.conflicts().is_empty()
+            && self.cage_sums_match()
+            && self.edges_hold()
+            && self.lines_hold()
+            && self.parities_hold()
+            && self.outside_clues_hold()
+            && self.data.iter().all(|c| {
+                let pos = (c.row_id, c.column_id);
+                self.chess_peers(pos)
+                    .iter()
+                    .all(|&(r, col)| self.data[[r - 1, col - 1]].value != c.value)
+            })
+    }
+    pub fn search(&mut self) -> bool {
+        self.calculate();
+        if self.is_error() {
+            return false;
+        }
+        if self.remaining_count().0 == 0 {
+            return self.is_solved();
+        }
+        let (pos, values) = match self.pick_cell() {
+            Some(p) => p,
+            None => return false,
+        };
+        let store = self.snapshot();
+        for v in values {
+            self.set_value(pos, v as u8);
+            if self.search() {
+                return true;
+            }
+            self.restore(&store);
+        }
+        false
+    }
+    // the empty cell with the fewest candidates
+    fn pick_cell(&self) -> Option<((usize, usize), Vec<usize>)> {
+        let mut best: Option<&Cell> = None;
+        for c in &self.data {
+            if c.value == 0 && best.is_none_or(|b| c.count() < b.count()) {
+                best = Some(c);
+            }
+        }
+        best.map(|c| ((c.row_id, c.column_id), c.get_values()))
     }
     pub fn try_guess(&mut self) {
         let mut ps = Vec::new();
@@ -641,15 +987,13 @@ impl Sudoku {
                 _ => (),
             };
         }
-        let store = self.to_string();
+        let store = self.snapshot();
         for p in ps {
             for i in p.1 {
                 self.set_value(p.0, i as u8);
                 self.calculate();
                 if !self.exam() {
-                    self.clean();
-                    self.init(&store);
-                    self.calculate();
+                    self.restore(&store);
                 } else {
                     return;
                 }
@@ -657,13 +1001,56 @@ impl Sudoku {
         }
     }
     pub fn try_value(&mut self, p: (usize, usize), v: u8) {
-        let store = self.to_string();
+        let store = self.snapshot();
         self.set_value(p, v);
         self.calculate();
         if self.is_error() {
-            self.clean();
-            self.init(&store);
-            self.calculate();
+            self.restore(&store);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Sudoku, SudokuError};
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn restore_brings_back_givens() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        let store = sudoku.snapshot();
+        sudoku.erase_value((1, 1));
+        sudoku.restore(&store);
+        let cell = sudoku.try_get_cell((1, 1)).unwrap();
+        assert_eq!(cell.value(), 5);
+        assert!(cell.is_given());
+    }
+
+    #[test]
+    fn try_erase_value_keeps_givens() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        assert_eq!(
+            sudoku.try_erase_value((1, 1)),
+            Err(SudokuError::GivenCell((1, 1), 5))
+        );
+        assert_eq!(sudoku.try_get_cell((1, 1)).unwrap().value(), 5);
+    }
+
+    // erasing a solved value gives it back to the empty peers
+    #[test]
+    fn erase_value_recomputes_peers() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.set_value((1, 3), 4);
+        assert!(!sudoku.try_get_cell((2, 2)).unwrap().check_bit(3));
+        assert!(!sudoku.try_get_cell((9, 3)).unwrap().check_bit(3));
+        sudoku.try_erase_value((1, 3)).unwrap();
+        assert!(sudoku.try_get_cell((2, 2)).unwrap().check_bit(3));
+        assert!(sudoku.try_get_cell((9, 3)).unwrap().check_bit(3));
+        assert!(sudoku.try_get_cell((1, 3)).unwrap().check_bit(3));
+    }
+}
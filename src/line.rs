@@ -0,0 +1,264 @@
+use crate::killer::cage_options;
+use crate::{value_char, Note, Sudoku, SudokuError};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineKind {
+    Thermo,          // strictly increasing from the bulb, the first cell
+    Arrow,           // the circle, the first cell, equals the sum of the rest
+    Sandwich(usize), // a whole row or column, the sum between 1 and the largest digit
+}
+impl fmt::Display for LineKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LineKind::Thermo => write!(f, "温度计"),
+            LineKind::Arrow => write!(f, "箭头"),
+            LineKind::Sandwich(sum) => write!(f, "三明治{}", sum),
+        }
+    }
+}
+
+// a constraint along a path of cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line {
+    pub kind: LineKind,
+    pub cells: Vec<(usize, usize)>,
+}
+
+fn low(grid: u32) -> usize {
+    grid.trailing_zeros() as usize + 1
+}
+fn high(grid: u32) -> usize {
+    32 - grid.leading_zeros() as usize
+}
+// digits lo..=hi as a candidate mask
+fn span(lo: usize, hi: usize) -> u32 {
+    (lo..=hi)
+        .filter(|&v| v >= 1)
+        .fold(0, |m, v| m | 1 << (v - 1))
+}
+
+impl Sudoku {
+    // thermometers and arrows follow a path of touching cells, diagonals included
+    pub fn add_line(
+        &mut self,
+        kind: LineKind,
+        cells: Vec<(usize, usize)>,
+    ) -> Result<usize, SudokuError> {
+        for (i, &pos) in cells.iter().enumerate() {
+            self.try_get_cell(pos)?;
+            let apart = i > 0 && {
+                let prev = cells[i - 1];
+                pos.0.abs_diff(prev.0) > 1 || pos.1.abs_diff(prev.1) > 1
+            };
+            if cells[..i].contains(&pos) || apart {
+                return Err(SudokuError::InvalidPath(pos));
+            }
+        }
+        if cells.len() < 2 || (kind == LineKind::Thermo && cells.len() > self.size) {
+            return Err(SudokuError::InvalidPath(
+                cells.last().copied().unwrap_or((0, 0)),
+            ));
+        }
+        self.lines.push(Line { kind, cells });
+        Ok(self.lines.len())
+    }
+    // sandwich clue for a row: the digits between 1 and size add up to sum
+    pub fn add_sandwich_row(&mut self, row: usize, sum: usize) -> Result<usize, SudokuError> {
+        self.try_get_row(row)?;
+        let cells = (1..=self.size).map(|c| (row, c)).collect();
+        self.add_sandwich(cells, sum)
+    }
+    pub fn add_sandwich_column(&mut self, column: usize, sum: usize) -> Result<usize, SudokuError> {
+        self.try_get_column(column)?;
+        let cells = (1..=self.size).map(|r| (r, column)).collect();
+        self.add_sandwich(cells, sum)
+    }
+    fn add_sandwich(
+        &mut self,
+        cells: Vec<(usize, usize)>,
+        sum: usize,
+    ) -> Result<usize, SudokuError> {
+        let max = (2..self.size).sum::<usize>();
+        if sum > max || sum == 1 {
+            return Err(SudokuError::InvalidSandwich(sum));
+        }
+        self.lines.push(Line {
+            kind: LineKind::Sandwich(sum),
+            cells,
+        });
+        Ok(self.lines.len())
+    }
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+    pub fn clear_lines(&mut self) {
+        self.lines.clear();
+    }
+    pub fn check_lines(&mut self) {
+        for i in 0..self.lines.len() {
+            let line = &self.lines[i];
+            let grids: Vec<u32> = line
+                .cells
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].grid)
+                .collect();
+            if grids.contains(&0) {
+                continue;
+            }
+            let allowed = match line.kind {
+                LineKind::Thermo => thermo_options(&grids),
+                LineKind::Arrow => arrow_options(&grids),
+                LineKind::Sandwich(sum) => sandwich_options(&grids, sum, self.size),
+            };
+            let (kind, cells) = (line.kind, line.cells.clone());
+            for (&(r, c), mask) in cells.iter().zip(allowed) {
+                for bit in 0..self.size {
+                    if mask & (1 << bit) == 0 && self.data[[r - 1, c - 1]].check_bit(bit) {
+                        self.data[[r - 1, c - 1]].log.push(Note::Text(format!(
+                            "{}{}上值{}不可能",
+                            kind,
+                            i + 1,
+                            value_char(bit as u8 + 1)
+                        )));
+                        self.data[[r - 1, c - 1]].clear_bit(bit);
+                    }
+                }
+            }
+        }
+    }
+    // every line whose cells are all filled keeps its rule
+    pub(crate) fn lines_hold(&self) -> bool {
+        self.lines.iter().all(|line| {
+            let values: Vec<usize> = line
+                .cells
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].value as usize)
+                .collect();
+            if values.contains(&0) {
+                return true;
+            }
+            match line.kind {
+                LineKind::Thermo => values.windows(2).all(|w| w[0] < w[1]),
+                LineKind::Arrow => values[0] == values[1..].iter().sum::<usize>(),
+                LineKind::Sandwich(sum) => {
+                    let a = values.iter().position(|&v| v == 1);
+                    let b = values.iter().position(|&v| v == self.size);
+                    match (a, b) {
+                        (Some(a), Some(b)) => {
+                            values[a.min(b) + 1..a.max(b)].iter().sum::<usize>() == sum
+                        }
+                        _ => false,
+                    }
+                }
+            }
+        })
+    }
+}
+
+// each cell above the lowest digit of the one before and below the highest after
+fn thermo_options(grids: &[u32]) -> Vec<u32> {
+    let mut masks = grids.to_vec();
+    for i in 1..masks.len() {
+        masks[i] &= !span(1, low(masks[i - 1]));
+        if masks[i] == 0 {
+            return masks;
+        }
+    }
+    for i in (0..masks.len() - 1).rev() {
+        masks[i] &= !span(high(masks[i + 1]), 32);
+        if masks[i] == 0 {
+            return masks;
+        }
+    }
+    masks
+}
+
+// bounds on the circle from the shaft and on each shaft cell from the rest
+fn arrow_options(grids: &[u32]) -> Vec<u32> {
+    let shaft = &grids[1..];
+    let min: usize = shaft.iter().map(|&g| low(g)).sum();
+    let max: usize = shaft.iter().map(|&g| high(g)).sum();
+    let circle = grids[0] & span(min, max);
+    let mut masks = vec![circle];
+    if circle == 0 {
+        masks.extend_from_slice(shaft);
+        return masks;
+    }
+    for &g in shaft {
+        let others_min = min - low(g);
+        let others_max = max - high(g);
+        let lo = low(circle).saturating_sub(others_max);
+        let hi = high(circle).saturating_sub(others_min);
+        masks.push(g & span(lo, hi));
+    }
+    masks
+}
+
+// union over every placement of 1 and the largest digit that can still make the sum
+fn sandwich_options(grids: &[u32], sum: usize, size: usize) -> Vec<u32> {
+    let one = 1;
+    let top = 1 << (size - 1);
+    let inner = ((1 << size) - 1) & !(one | top);
+    let mut allowed = vec![0; grids.len()];
+    for i in 0..grids.len() {
+        for j in 0..grids.len() {
+            if i == j || grids[i] & one == 0 || grids[j] & top == 0 {
+                continue;
+            }
+            let (a, b) = (i.min(j), i.max(j));
+            let between: Vec<u32> = grids[a + 1..b].iter().map(|g| g & inner).collect();
+            if between.contains(&0) {
+                continue;
+            }
+            // the digits in between are distinct, as in a cage
+            let options = match between.len() {
+                0 if sum == 0 => Vec::new(),
+                0 => continue,
+                _ => cage_options(&between, sum),
+            };
+            if options.contains(&0) {
+                continue;
+            }
+            allowed[i] |= one;
+            allowed[j] |= top;
+            for (n, mask) in options.into_iter().enumerate() {
+                allowed[a + 1 + n] |= mask;
+            }
+            for (n, &g) in grids.iter().enumerate() {
+                if n < a || n > b {
+                    allowed[n] |= g & inner;
+                }
+            }
+        }
+    }
+    allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sandwich_options;
+    use crate::Sudoku;
+
+    const ANY: u32 = 0b1111;
+
+    // 5 between 1 and 4 needs both 2 and 3, so 1 and 4 sit at the ends
+    #[test]
+    fn sandwich_puts_the_crust_at_the_ends() {
+        let allowed = sandwich_options(&[ANY; 4], 5, 4);
+        assert_eq!(allowed, vec![0b1001, 0b0110, 0b0110, 0b1001]);
+    }
+
+    #[test]
+    fn sandwich_clue_restricts_the_column() {
+        let mut sudoku = Sudoku::with_size(2, 2).unwrap();
+        sudoku.add_sandwich_column(3, 5).unwrap();
+        sudoku.check_lines();
+        let column: Vec<Vec<usize>> = (1..=4)
+            .map(|r| sudoku.try_get_cell((r, 3)).unwrap().get_values())
+            .collect();
+        assert_eq!(column, vec![vec![1, 4], vec![2, 3], vec![2, 3], vec![1, 4]]);
+    }
+}
@@ -1,7 +1,7 @@
 use std::io::Write;
 use std::time::Instant;
 use std::{fs, fs::File, io};
-use sudoku::Sudoku;
+use sudoku::{Highlight, Snapshot, Step, Sudoku, SudokuError};
 
 fn main() {
     println!("*********************************************************************************");
@@ -13,9 +13,9 @@ fn main() {
     };
     'toploop: loop {
         let mut sudoku: Sudoku = Sudoku::new();
-        let mut str = String::new();
-        while str.trim().len() != 81 {
-            println!("请输入81个数字,未知数字请输入0,可以三位一组输入,参考下行的位置编号:");
+        let mut str;
+        loop {
+            println!("请输入81个数字,未知数字请输入0,可以三位一组输入,参考下行的位置编号(也可以输入png/jpg图片的文件名):");
             println!(
                 "123456789123456789123456789123456789123456789123456789123456789123456789123456789"
             );
@@ -24,8 +24,40 @@ fn main() {
                 Ok(_) => (),
                 Err(_) => println!("输入错误！"),
             };
+            // a screenshot or scan instead of typing the digits
+            let name = str.trim().to_ascii_lowercase();
+            if [".png", ".jpg", ".jpeg"]
+                .iter()
+                .any(|ext| name.ends_with(ext))
+            {
+                let digits = match fs::read(str.trim()) {
+                    Ok(bytes) => Sudoku::recognize_image(&bytes).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                match digits {
+                    Ok(digits) => {
+                        println!("识别结果: {}", digits);
+                        str = digits + "\n";
+                    }
+                    Err(e) => {
+                        println!("无法读取图片: {}", e);
+                        continue;
+                    }
+                }
+            }
+            match sudoku.try_init(&str) {
+                Ok(_) => break,
+                Err(SudokuError::Conflict { .. }) => {
+                    let mut check = Sudoku::new();
+                    check.init(&str);
+                    println!("题目有冲突:");
+                    for c in check.conflicts() {
+                        println!("  {}", c);
+                    }
+                }
+                Err(e) => println!("输入错误: {}", e),
+            }
         }
-        sudoku.init(&str);
         sudoku.print();
         let remain = sudoku.remaining_count();
         println!("**** Remain {:?}", remain);
@@ -33,8 +65,8 @@ fn main() {
         let mut buffer = String::new();
         io::stdin().read_line(&mut buffer).unwrap();
         let start = Instant::now();
-        sudoku.calculate();
-        sudoku.print();
+        let steps = sudoku.calculate_steps();
+        sudoku.print_highlight(&latest_step(&steps));
         if !sudoku.exam() {
             println!("开始尝试猜测...");
             sudoku.try_guess();
@@ -45,18 +77,31 @@ fn main() {
         let end = Instant::now();
         let duration = end.duration_since(start).as_secs_f32();
         println!("程序执行时间：{:.4} 秒", duration);
+        let mut history: Vec<Snapshot> = Vec::new();
         loop {
-            println!("请输入操作命令:(guess|reset|new|save|quit)");
+            println!("请输入操作命令:(guess|undo|reset|new|save|quit)");
             let mut cmd = String::new();
 
             io::stdin().read_line(&mut cmd).expect("错误的命令!");
             match cmd.trim() {
                 "guess" => (),
+                "undo" => {
+                    match history.pop() {
+                        Some(snapshot) => {
+                            sudoku.restore(&snapshot);
+                            sudoku.print();
+                            sudoku.exam();
+                        }
+                        None => println!("没有可以撤销的操作."),
+                    }
+                    continue;
+                }
                 "reset" => {
+                    history.clear();
                     sudoku.clean();
                     sudoku.init(&str);
-                    sudoku.calculate();
-                    sudoku.print();
+                    let steps = sudoku.calculate_steps();
+                    sudoku.print_highlight(&latest_step(&steps));
                     if !sudoku.exam() {
                         println!("开始尝试猜测...");
                         sudoku.try_guess();
@@ -69,14 +114,14 @@ fn main() {
                     break 'toploop;
                 }
                 "save" => {
-                    file.write_all(sudoku.to_string().as_bytes())
+                    file.write_all(format!("{}\r\n", sudoku).as_bytes())
                         .expect("写入log文件失败！");
                     let _ = file.flush();
                 }
                 _ => continue,
             }
             loop {
-                println!("请输入行列号来查看或者猜测对应格子\n;例如 : 34=>3行4列的格子 | 345=>将3行4列设置为5\n输入0=>退出猜测");
+                println!("请输入行列号来查看或者猜测对应格子\n;例如 : 34=>3行4列的格子 | 345=>将3行4列设置为5 | 340=>清除3行4列的值 | 100=>查看整盘候选值\n输入0=>退出猜测");
                 let mut pos = String::new();
                 io::stdin().read_line(&mut pos).unwrap();
                 let pos: usize = match pos.trim().parse() {
@@ -91,29 +136,47 @@ fn main() {
                     10 | 20 | 30 | 40 | 50 | 60 | 70 | 80 | 90 => continue,
                     11..=99 => sudoku.print_cell((pos - pos % 10) / 10, pos % 10),
                     100 => {
-                        for i in 1..10 {
-                            for j in 1..10 {
-                                sudoku.print_cell(i, j);
-                            }
-                        }
+                        sudoku.print();
+                        println!("**** Remain {:?}", sudoku.remaining_count());
                     }
                     111..=999 => {
-                        if pos % 10 != 0 {
+                        if pos.is_multiple_of(10) {
+                            let r = (pos - pos % 100) / 100;
+                            let c = (pos % 100) / 10;
+                            let snapshot = sudoku.snapshot();
+                            if let Err(e) = sudoku.try_erase_value((r, c)) {
+                                println!("{}", e);
+                                continue;
+                            }
+                            history.push(snapshot);
+                            sudoku.print_highlight(&Highlight {
+                                cells: vec![(r, c)],
+                                ..Default::default()
+                            });
+                            sudoku.exam();
+                        } else {
                             let r = (pos - pos % 100) / 100;
                             let c = (pos % 100 - pos % 10) / 10;
                             let v = pos % 10;
-                            sudoku.set_value((r, c), v as u8);
-                            sudoku.calculate();
-                            sudoku.print();
+                            let snapshot = sudoku.snapshot();
+                            if let Err(e) = sudoku.try_set_value((r, c), v as u8) {
+                                println!("{}", e);
+                                continue;
+                            }
+                            history.push(snapshot);
+                            let steps = sudoku.calculate_steps();
+                            let mut highlight = latest_step(&steps);
+                            highlight.cells.insert(0, (r, c));
+                            sudoku.print_highlight(&highlight);
                             if !sudoku.exam() {
                                 println!("开始尝试猜测...");
                                 sudoku.try_guess();
                                 sudoku.print();
                             }
                             if sudoku.is_error() {
-                                sudoku.clean();
-                                sudoku.init(&str);
-                                sudoku.calculate();
+                                if let Some(snapshot) = history.pop() {
+                                    sudoku.restore(&snapshot);
+                                }
                                 sudoku.print();
                             }
                         }
@@ -125,3 +188,8 @@ fn main() {
     }
     println!("*********************************************************************************");
 }
+
+// the cell and eliminations of the last step only, not the whole run
+fn latest_step(steps: &[Step]) -> Highlight {
+    Highlight::from_steps(&steps[steps.len().saturating_sub(1)..])
+}
@@ -0,0 +1,406 @@
+use crate::{char_value, value_char, Note, Snapshot, Sudoku, SudokuError};
+use std::fmt;
+
+// Overlapping 9x9 grids on one larger board, e.g. Samurai. Positions are
+// global (row, column), 1-based; a cell inside several grids is shared and
+// every value or elimination is copied to all of them.
+//
+// Text format: "layout samurai" (or twodoku, butterfly, or the grid corners
+// as "layout 0:0 6:6") followed by the rows of the whole board, blanks as
+// '0' or '.', spaces where no grid covers it:
+//
+//   layout twodoku
+//   ..3.2.6..
+//   9..3.5..1
+//   ..18.64..
+//   ..81.29..
+//   7.......8
+//   ..67.82..
+//   2..5.1......4.
+//   ...
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawMultiSudoku"))]
+pub struct MultiSudoku {
+    grids: Vec<Sudoku>,
+    offsets: Vec<(usize, usize)>, // top-left corner of each grid, 0-based
+}
+// a saved board goes through with_offsets, each grid must be 9x9
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawMultiSudoku {
+    grids: Vec<Sudoku>,
+    offsets: Vec<(usize, usize)>,
+}
+#[cfg(feature = "serde")]
+impl TryFrom<RawMultiSudoku> for MultiSudoku {
+    type Error = SudokuError;
+    fn try_from(raw: RawMultiSudoku) -> Result<MultiSudoku, SudokuError> {
+        if raw.grids.len() != raw.offsets.len() {
+            return Err(SudokuError::InvalidLength(
+                raw.grids.len(),
+                raw.offsets.len(),
+            ));
+        }
+        if let Some(g) = raw.grids.iter().find(|g| g.size() != 9) {
+            let (r, c) = g.box_shape();
+            return Err(SudokuError::InvalidSize(r, c));
+        }
+        let mut multi = MultiSudoku::with_offsets(raw.offsets)?;
+        multi.grids = raw.grids;
+        Ok(multi)
+    }
+}
+impl fmt::Display for MultiSudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (rows, columns) = self.dimensions();
+        for r in 1..=rows {
+            let mut line = String::new();
+            for c in 1..=columns {
+                line.push(match self.value((r, c)) {
+                    None => ' ',
+                    Some(0) => '.',
+                    Some(v) => value_char(v),
+                });
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl MultiSudoku {
+    // grids that overlap must share whole 3x3 blocks, so their corners lie
+    // a multiple of 3 apart
+    pub fn with_offsets(offsets: Vec<(usize, usize)>) -> Result<MultiSudoku, SudokuError> {
+        if offsets.is_empty() {
+            return Err(SudokuError::EmptyLayout);
+        }
+        for (i, &a) in offsets.iter().enumerate() {
+            for &b in &offsets[i + 1..] {
+                let (dr, dc) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+                if dr < 9 && dc < 9 && !(dr.is_multiple_of(3) && dc.is_multiple_of(3)) {
+                    return Err(SudokuError::InvalidOverlap(a, b));
+                }
+            }
+        }
+        let grids = offsets.iter().map(|_| Sudoku::new()).collect();
+        Ok(MultiSudoku { grids, offsets })
+    }
+    // five grids, the centre one sharing a corner box with each of the others
+    pub fn samurai() -> MultiSudoku {
+        let offsets = vec![(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+        MultiSudoku::with_offsets(offsets).unwrap()
+    }
+    // two grids sharing one corner box
+    pub fn twodoku() -> MultiSudoku {
+        MultiSudoku::with_offsets(vec![(0, 0), (6, 6)]).unwrap()
+    }
+    // four grids on a 12x12 board, each pair overlapping by two box columns or rows
+    pub fn butterfly() -> MultiSudoku {
+        MultiSudoku::with_offsets(vec![(0, 0), (0, 3), (3, 0), (3, 3)]).unwrap()
+    }
+    pub fn grids(&self) -> &[Sudoku] {
+        &self.grids
+    }
+    // for variant rules on a single grid; shared cells are synced by calculate
+    pub fn grid_mut(&mut self, index: usize) -> Option<&mut Sudoku> {
+        self.grids.get_mut(index)
+    }
+    pub fn offsets(&self) -> &[(usize, usize)] {
+        &self.offsets
+    }
+    pub fn dimensions(&self) -> (usize, usize) {
+        let rows = self.offsets.iter().map(|o| o.0 + 9).max().unwrap_or(0);
+        let columns = self.offsets.iter().map(|o| o.1 + 9).max().unwrap_or(0);
+        (rows, columns)
+    }
+    // the grids covering a global position, with the local position in each
+    pub fn owners(&self, pos: (usize, usize)) -> Vec<(usize, (usize, usize))> {
+        let mut result = Vec::new();
+        for (i, &(r, c)) in self.offsets.iter().enumerate() {
+            if pos.0 > r && pos.0 <= r + 9 && pos.1 > c && pos.1 <= c + 9 {
+                result.push((i, (pos.0 - r, pos.1 - c)));
+            }
+        }
+        result
+    }
+    // every covered position, row by row
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let (rows, columns) = self.dimensions();
+        let mut result = Vec::new();
+        for r in 1..=rows {
+            for c in 1..=columns {
+                if !self.owners((r, c)).is_empty() {
+                    result.push((r, c));
+                }
+            }
+        }
+        result
+    }
+    // None outside the grids, Some(0) for an empty cell
+    pub fn value(&self, pos: (usize, usize)) -> Option<u8> {
+        let (i, local) = *self.owners(pos).first()?;
+        Some(self.grids[i].data[[local.0 - 1, local.1 - 1]].value)
+    }
+    pub fn set_value(&mut self, pos: (usize, usize), value: u8) {
+        for (i, local) in self.owners(pos) {
+            self.grids[i].set_value(local, value);
+        }
+    }
+    pub fn try_set_value(&mut self, pos: (usize, usize), value: u8) -> Result<(), SudokuError> {
+        let owners = self.owners(pos);
+        if owners.is_empty() {
+            return Err(SudokuError::InvalidPosition(pos.0, pos.1));
+        }
+        let store = self.snapshot();
+        for (i, local) in owners {
+            let offset = self.offsets[i];
+            // report positions on the whole board
+            let global = |p: (usize, usize)| (p.0 + offset.0, p.1 + offset.1);
+            if let Err(e) = self.grids[i].try_set_value(local, value) {
+                self.restore(&store);
+                return Err(match e {
+                    SudokuError::CellFilled(p, v) => SudokuError::CellFilled(global(p), v),
+                    SudokuError::Conflict { pos, value, with } => SudokuError::Conflict {
+                        pos: global(pos),
+                        value,
+                        with: global(with),
+                    },
+                    e => e,
+                });
+            }
+        }
+        Ok(())
+    }
+    // rows of the whole board, spaces where no grid is
+    pub fn try_init(&mut self, text: &str) -> Result<(), SudokuError> {
+        let rows: Vec<Vec<char>> = text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.chars().collect())
+            .collect();
+        let (height, width) = self.dimensions();
+        if rows.len() != height {
+            return Err(SudokuError::InvalidLength(rows.len(), height));
+        }
+        let store = self.snapshot();
+        for (r, row) in rows.iter().enumerate() {
+            for c in 0..width.max(row.len()) {
+                let ch = row.get(c).copied().unwrap_or(' ');
+                let pos = (r + 1, c + 1);
+                let covered = !self.owners(pos).is_empty();
+                let result = match (covered, ch, char_value(ch)) {
+                    (false, ' ', _) => Ok(()),
+                    (true, '0' | '.', _) => Ok(()),
+                    (true, _, Some(v)) if v <= 9 => self.try_set_value(pos, v),
+                    _ => Err(SudokuError::InvalidDigit(r * width + c + 1, ch)),
+                };
+                if let Err(e) = result {
+                    self.restore(&store);
+                    return Err(e);
+                }
+            }
+        }
+        for sudoku in &mut self.grids {
+            sudoku.mark_givens();
+        }
+        Ok(())
+    }
+    pub fn parse(text: &str) -> Result<MultiSudoku, SudokuError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        let (n, first) = lines.next().ok_or(SudokuError::InvalidLine(1))?;
+        let mut multi = match first.split_whitespace().collect::<Vec<_>>()[..] {
+            ["layout", "samurai"] => MultiSudoku::samurai(),
+            ["layout", "twodoku"] => MultiSudoku::twodoku(),
+            ["layout", "butterfly"] => MultiSudoku::butterfly(),
+            ["layout", ref corners @ ..] => {
+                let offsets: Option<Vec<(usize, usize)>> = corners
+                    .iter()
+                    .map(|t| {
+                        let (r, c) = t.split_once(':')?;
+                        Some((r.parse().ok()?, c.parse().ok()?))
+                    })
+                    .collect();
+                match offsets {
+                    Some(offsets) => MultiSudoku::with_offsets(offsets)?,
+                    None => return Err(SudokuError::InvalidLine(n + 1)),
+                }
+            }
+            _ => return Err(SudokuError::InvalidLine(n + 1)),
+        };
+        let rows: Vec<&str> = lines.map(|(_, l)| l).collect();
+        multi.try_init(&rows.join("\n"))?;
+        Ok(multi)
+    }
+    pub fn to_text(&self) -> String {
+        let name = match &self.offsets[..] {
+            [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)] => String::from("samurai"),
+            [(0, 0), (6, 6)] => String::from("twodoku"),
+            [(0, 0), (0, 3), (3, 0), (3, 3)] => String::from("butterfly"),
+            offsets => {
+                let corners: Vec<String> =
+                    offsets.iter().map(|o| format!("{}:{}", o.0, o.1)).collect();
+                corners.join(" ")
+            }
+        };
+        format!("layout {}\n{}", name, self)
+    }
+    pub fn snapshot(&self) -> Vec<Snapshot> {
+        self.grids.iter().map(|g| g.snapshot()).collect()
+    }
+    pub fn restore(&mut self, snapshot: &[Snapshot]) {
+        for (g, s) in self.grids.iter_mut().zip(snapshot) {
+            g.restore(s);
+        }
+    }
+    // every grid's snapshot is checked before any of them is restored
+    pub fn try_restore(&mut self, snapshot: &[Snapshot]) -> Result<(), SudokuError> {
+        if snapshot.len() != self.grids.len() {
+            return Err(SudokuError::InvalidLength(snapshot.len(), self.grids.len()));
+        }
+        for (g, s) in self.grids.iter().zip(snapshot) {
+            g.check_snapshot(s)?;
+        }
+        self.restore(snapshot);
+        Ok(())
+    }
+    // copy values and eliminations between the grids sharing a cell
+    fn sync(&mut self) {
+        for pos in self.cells() {
+            let owners = self.owners(pos);
+            if owners.len() < 2 {
+                continue;
+            }
+            let mut grid = u32::MAX;
+            let mut value = 0;
+            for &(i, (r, c)) in &owners {
+                let cell = &self.grids[i].data[[r - 1, c - 1]];
+                grid &= cell.grid;
+                if cell.value != 0 {
+                    value = cell.value;
+                }
+            }
+            for (i, (r, c)) in owners {
+                let sudoku = &mut self.grids[i];
+                if value != 0 && sudoku.data[[r - 1, c - 1]].value == 0 {
+                    sudoku.data[[r - 1, c - 1]].log.push(Note::Text(format!(
+                        "值 {} 来自重叠的格子",
+                        value_char(value)
+                    )));
+                    sudoku.set_value((r, c), value);
+                }
+                for bit in 0..9 {
+                    if grid & (1 << bit) == 0 {
+                        sudoku.data[[r - 1, c - 1]].clear_bit(bit);
+                    }
+                }
+            }
+        }
+    }
+    pub fn remaining_count(&self) -> (usize, usize) {
+        let mut result = (0, 0);
+        for g in &self.grids {
+            let (empty, candidates) = g.remaining_count();
+            result = (result.0 + empty, result.1 + candidates);
+        }
+        result
+    }
+    pub fn calculate(&mut self) {
+        loop {
+            let count = self.remaining_count();
+            for g in &mut self.grids {
+                g.calculate();
+            }
+            self.sync();
+            if count == self.remaining_count() {
+                break;
+            }
+        }
+    }
+    pub fn is_error(&self) -> bool {
+        self.grids.iter().any(|g| g.is_error())
+    }
+    pub fn is_solved(&self) -> bool {
+        self.grids.iter().all(|g| g.is_solved())
+            && self.cells().iter().all(|&pos| {
+                let owners = self.owners(pos);
+                let (i, (r, c)) = owners[0];
+                let value = self.grids[i].data[[r - 1, c - 1]].value;
+                owners
+                    .iter()
+                    .all(|&(j, (r, c))| self.grids[j].data[[r - 1, c - 1]].value == value)
+            })
+    }
+    // the empty cell with the fewest candidates, as a global position
+    fn pick_cell(&self) -> Option<((usize, usize), Vec<usize>)> {
+        let mut best: Option<((usize, usize), Vec<usize>)> = None;
+        for (i, g) in self.grids.iter().enumerate() {
+            let (r, c) = self.offsets[i];
+            for cell in &g.data {
+                if cell.value == 0 && best.as_ref().is_none_or(|b| cell.count() < b.1.len()) {
+                    let pos = (cell.row_id + r, cell.column_id + c);
+                    best = Some((pos, cell.get_values()));
+                }
+            }
+        }
+        best
+    }
+    pub fn search(&mut self) -> bool {
+        self.calculate();
+        if self.is_error() {
+            return false;
+        }
+        if self.remaining_count().0 == 0 {
+            return self.is_solved();
+        }
+        let (pos, values) = match self.pick_cell() {
+            Some(p) => p,
+            None => return false,
+        };
+        let store = self.snapshot();
+        for v in values {
+            self.set_value(pos, v as u8);
+            if self.search() {
+                return true;
+            }
+            self.restore(&store);
+        }
+        false
+    }
+    // counting stops once limit solutions are found
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        let mut multi = self.clone();
+        let mut count = 0;
+        multi.count_search(limit, &mut count);
+        count
+    }
+    fn count_search(&mut self, limit: usize, count: &mut usize) {
+        self.calculate();
+        if self.is_error() {
+            return;
+        }
+        if self.remaining_count().0 == 0 {
+            if self.is_solved() {
+                *count += 1;
+            }
+            return;
+        }
+        let (pos, values) = match self.pick_cell() {
+            Some(p) => p,
+            None => return,
+        };
+        let store = self.snapshot();
+        for v in values {
+            self.set_value(pos, v as u8);
+            self.count_search(limit, count);
+            self.restore(&store);
+            if *count >= limit {
+                return;
+            }
+        }
+    }
+}
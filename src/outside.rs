@@ -0,0 +1,321 @@
+use crate::killer::cage_options;
+use crate::{value_char, Note, Sudoku, SudokuError};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutsideKind {
+    LittleKiller, // sum along a diagonal, digits may repeat
+    XSum,         // sum of the first N cells, N being the first digit
+    Skyscraper,   // how many digits are seen, taller ones hiding lower ones
+}
+impl fmt::Display for OutsideKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutsideKind::LittleKiller => write!(f, "小杀手"),
+            OutsideKind::XSum => write!(f, "X和"),
+            OutsideKind::Skyscraper => write!(f, "摩天楼"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// a clue outside the grid, read from the edge cell start stepping inwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutsideClue {
+    pub kind: OutsideKind,
+    pub start: (usize, usize),
+    pub step: (isize, isize),
+    pub value: usize,
+}
+
+impl Sudoku {
+    // the cells a clue looks at, from the edge inwards
+    pub fn clue_cells(&self, clue: &OutsideClue) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        let (mut r, mut c) = (clue.start.0 as isize, clue.start.1 as isize);
+        let size = self.size as isize;
+        while (1..=size).contains(&r) && (1..=size).contains(&c) {
+            cells.push((r as usize, c as usize));
+            r += clue.step.0;
+            c += clue.step.1;
+        }
+        cells
+    }
+    // little killer: the diagonal from an edge cell, step being (±1, ±1)
+    pub fn add_little_killer(
+        &mut self,
+        start: (usize, usize),
+        step: (isize, isize),
+        sum: usize,
+    ) -> Result<usize, SudokuError> {
+        self.try_get_cell(start)?;
+        let (r, c) = (start.0 as isize - step.0, start.1 as isize - step.1);
+        let size = self.size as isize;
+        let outside = !(1..=size).contains(&r) || !(1..=size).contains(&c);
+        if step.0.abs() != 1 || step.1.abs() != 1 || !outside {
+            return Err(SudokuError::InvalidPath(start));
+        }
+        self.add_outside(OutsideClue {
+            kind: OutsideKind::LittleKiller,
+            start,
+            step,
+            value: sum,
+        })
+    }
+    pub fn add_x_sum(
+        &mut self,
+        side: Side,
+        index: usize,
+        sum: usize,
+    ) -> Result<usize, SudokuError> {
+        let clue = self.side_clue(OutsideKind::XSum, side, index, sum)?;
+        self.add_outside(clue)
+    }
+    pub fn add_skyscraper(
+        &mut self,
+        side: Side,
+        index: usize,
+        count: usize,
+    ) -> Result<usize, SudokuError> {
+        if count == 0 || count > self.size {
+            return Err(SudokuError::InvalidClue(count));
+        }
+        let clue = self.side_clue(OutsideKind::Skyscraper, side, index, count)?;
+        self.add_outside(clue)
+    }
+    // a clue reading row or column index from one side
+    fn side_clue(
+        &self,
+        kind: OutsideKind,
+        side: Side,
+        index: usize,
+        value: usize,
+    ) -> Result<OutsideClue, SudokuError> {
+        if !(1..=self.size).contains(&index) {
+            return Err(SudokuError::InvalidIndex(index));
+        }
+        let (start, step) = match side {
+            Side::Top => ((1, index), (1, 0)),
+            Side::Bottom => ((self.size, index), (-1, 0)),
+            Side::Left => ((index, 1), (0, 1)),
+            Side::Right => ((index, self.size), (0, -1)),
+        };
+        Ok(OutsideClue {
+            kind,
+            start,
+            step,
+            value,
+        })
+    }
+    // which side a row or column clue is read from
+    pub fn clue_side(&self, clue: &OutsideClue) -> Option<(Side, usize)> {
+        match clue.step {
+            (1, 0) if clue.start.0 == 1 => Some((Side::Top, clue.start.1)),
+            (-1, 0) if clue.start.0 == self.size => Some((Side::Bottom, clue.start.1)),
+            (0, 1) if clue.start.1 == 1 => Some((Side::Left, clue.start.0)),
+            (0, -1) if clue.start.1 == self.size => Some((Side::Right, clue.start.0)),
+            _ => None,
+        }
+    }
+    fn add_outside(&mut self, clue: OutsideClue) -> Result<usize, SudokuError> {
+        let n = self.clue_cells(&clue).len();
+        let value = clue.value;
+        let max = match clue.kind {
+            OutsideKind::LittleKiller => n * self.size,
+            _ => self.size * (self.size + 1) / 2,
+        };
+        if value == 0 || value > max {
+            return Err(SudokuError::InvalidClue(value));
+        }
+        self.outside.push(clue);
+        Ok(self.outside.len())
+    }
+    pub fn outside_clues(&self) -> &[OutsideClue] {
+        &self.outside
+    }
+    pub fn clear_outside_clues(&mut self) {
+        self.outside.clear();
+    }
+    pub fn check_outside_clues(&mut self) {
+        for i in 0..self.outside.len() {
+            let clue = self.outside[i];
+            let cells = self.clue_cells(&clue);
+            let grids: Vec<u32> = cells
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].grid)
+                .collect();
+            if grids.contains(&0) {
+                continue;
+            }
+            let allowed = match clue.kind {
+                OutsideKind::LittleKiller => {
+                    self.restrict_sum(&cells, clue.value, &clue.kind.to_string());
+                    continue;
+                }
+                OutsideKind::XSum => x_sum_options(&grids, clue.value),
+                OutsideKind::Skyscraper => skyscraper_options(&grids, clue.value),
+            };
+            for (&(r, c), mask) in cells.iter().zip(allowed) {
+                for bit in 0..self.size {
+                    if mask & (1 << bit) == 0 && self.data[[r - 1, c - 1]].check_bit(bit) {
+                        self.data[[r - 1, c - 1]].log.push(Note::Text(format!(
+                            "{}提示{}, 值{}不可能",
+                            clue.kind,
+                            clue.value,
+                            value_char(bit as u8 + 1)
+                        )));
+                        self.data[[r - 1, c - 1]].clear_bit(bit);
+                    }
+                }
+            }
+        }
+    }
+    pub(crate) fn outside_clues_hold(&self) -> bool {
+        self.outside.iter().all(|clue| {
+            let values: Vec<usize> = self
+                .clue_cells(clue)
+                .iter()
+                .map(|&(r, c)| self.data[[r - 1, c - 1]].value as usize)
+                .collect();
+            if values.contains(&0) {
+                return true;
+            }
+            let result = match clue.kind {
+                OutsideKind::LittleKiller => values.iter().sum(),
+                OutsideKind::XSum => values.iter().take(values[0]).sum(),
+                OutsideKind::Skyscraper => {
+                    let mut top = 0;
+                    let mut seen = 0;
+                    for &v in &values {
+                        if v > top {
+                            top = v;
+                            seen += 1;
+                        }
+                    }
+                    seen
+                }
+            };
+            result == clue.value
+        })
+    }
+}
+
+// for each possible first digit n, the first n cells are distinct digits
+// adding up to sum, as in a cage
+fn x_sum_options(grids: &[u32], sum: usize) -> Vec<u32> {
+    let mut allowed = vec![0; grids.len()];
+    for n in 1..=grids.len() {
+        if grids[0] & (1 << (n - 1)) == 0 {
+            continue;
+        }
+        let mut head = grids[..n].to_vec();
+        head[0] = 1 << (n - 1);
+        let options = cage_options(&head, sum);
+        if options.contains(&0) {
+            continue;
+        }
+        for (i, mask) in options.into_iter().enumerate() {
+            allowed[i] |= mask;
+        }
+        for i in n..grids.len() {
+            allowed[i] |= grids[i];
+        }
+    }
+    allowed
+}
+
+// exact support: a digit stays when some filling of the row or column
+// with distinct candidates shows count skyscrapers
+fn skyscraper_options(grids: &[u32], count: usize) -> Vec<u32> {
+    let mut allowed = vec![0; grids.len()];
+    let mut memo = HashMap::new();
+    skyscraper_search(grids, count, (0, 0, 0, 0), &mut allowed, &mut memo);
+    allowed
+}
+// state: index, used digits, tallest so far, skyscrapers seen
+fn skyscraper_search(
+    grids: &[u32],
+    count: usize,
+    state: (usize, u32, usize, usize),
+    allowed: &mut [u32],
+    memo: &mut HashMap<(usize, u32, usize, usize), bool>,
+) -> bool {
+    let (i, used, top, seen) = state;
+    if i == grids.len() {
+        return seen == count;
+    }
+    // every remaining cell taller than the rest is the most that can still be seen
+    let size = grids.len();
+    if seen > count || seen + (size - i).min(size - top) < count {
+        return false;
+    }
+    if let Some(&found) = memo.get(&state) {
+        return found;
+    }
+    let mut found = false;
+    let mut free = grids[i] & !used;
+    while free != 0 {
+        let bit = free & free.wrapping_neg();
+        free ^= bit;
+        let v = bit.trailing_zeros() as usize + 1;
+        let next = match v > top {
+            true => (i + 1, used | bit, v, seen + 1),
+            false => (i + 1, used | bit, top, seen),
+        };
+        if skyscraper_search(grids, count, next, allowed, memo) {
+            allowed[i] |= bit;
+            found = true;
+        }
+    }
+    memo.insert(state, found);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{skyscraper_options, x_sum_options};
+    use crate::{Side, Sudoku};
+
+    const ANY: u32 = 0b1111;
+
+    #[test]
+    fn skyscrapers_all_seen_climb_in_order() {
+        let allowed = skyscraper_options(&[ANY; 4], 4);
+        assert_eq!(allowed, vec![0b0001, 0b0010, 0b0100, 0b1000]);
+    }
+
+    #[test]
+    fn one_skyscraper_seen_is_the_tallest() {
+        let allowed = skyscraper_options(&[ANY; 4], 1);
+        assert_eq!(allowed, vec![0b1000, 0b0111, 0b0111, 0b0111]);
+    }
+
+    // 10 in a 4x4 row can only be all four digits, so the first is 4
+    #[test]
+    fn x_sum_fixes_the_count() {
+        let allowed = x_sum_options(&[ANY; 4], 10);
+        assert_eq!(allowed, vec![0b1000, 0b0111, 0b0111, 0b0111]);
+    }
+
+    #[test]
+    fn skyscraper_clue_restricts_the_row() {
+        let mut sudoku = Sudoku::with_size(2, 2).unwrap();
+        sudoku.add_skyscraper(Side::Right, 2, 4).unwrap();
+        sudoku.check_outside_clues();
+        let row: Vec<Vec<usize>> = (1..=4)
+            .map(|c| sudoku.try_get_cell((2, c)).unwrap().get_values())
+            .collect();
+        assert_eq!(row, vec![vec![4], vec![3], vec![2], vec![1]]);
+    }
+}
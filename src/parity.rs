@@ -0,0 +1,81 @@
+use crate::{value_char, Note, Sudoku, SudokuError};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Parity {
+    Even, // shaded square
+    Odd,  // shaded circle
+}
+impl Parity {
+    pub fn allows(self, value: u8) -> bool {
+        match self {
+            Parity::Even => value.is_multiple_of(2),
+            Parity::Odd => !value.is_multiple_of(2),
+        }
+    }
+    // candidate mask of the allowed digits 1~size
+    pub fn mask(self, size: usize) -> u32 {
+        (1..=size as u8)
+            .filter(|&v| self.allows(v))
+            .fold(0, |m, v| m | 1 << (v - 1))
+    }
+}
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Parity::Even => write!(f, "偶数格"),
+            Parity::Odd => write!(f, "奇数格"),
+        }
+    }
+}
+
+impl Sudoku {
+    // even/odd clue on a cell, replacing any earlier one
+    pub fn set_parity(&mut self, pos: (usize, usize), parity: Parity) -> Result<(), SudokuError> {
+        self.try_get_cell(pos)?;
+        self.parities.retain(|p| p.0 != pos);
+        self.parities.push((pos, parity));
+        self.apply_parities();
+        Ok(())
+    }
+    pub fn parity(&self, pos: (usize, usize)) -> Option<Parity> {
+        self.parities.iter().find(|p| p.0 == pos).map(|p| p.1)
+    }
+    pub fn parities(&self) -> &[((usize, usize), Parity)] {
+        &self.parities
+    }
+    pub fn clear_parities(&mut self) {
+        self.parities.clear();
+    }
+    // the candidates a cell may ever hold
+    pub(crate) fn parity_mask(&self, pos: (usize, usize)) -> u32 {
+        match self.parity(pos) {
+            Some(parity) => parity.mask(self.size),
+            None => self.full_grid(),
+        }
+    }
+    pub(crate) fn apply_parities(&mut self) {
+        for i in 0..self.parities.len() {
+            let ((r, c), parity) = self.parities[i];
+            let mask = parity.mask(self.size);
+            let cell = &mut self.data[[r - 1, c - 1]];
+            for bit in 0..self.size {
+                if mask & (1 << bit) == 0 && cell.check_bit(bit) {
+                    cell.log.push(Note::Text(format!(
+                        "{}, 值{}不可能",
+                        parity,
+                        value_char(bit as u8 + 1)
+                    )));
+                    cell.clear_bit(bit);
+                }
+            }
+        }
+    }
+    pub(crate) fn parities_hold(&self) -> bool {
+        self.parities.iter().all(|&((r, c), parity)| {
+            let value = self.data[[r - 1, c - 1]].value;
+            value == 0 || parity.allows(value)
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{char_value, value_char, Note, Sudoku, SudokuError};
+
+// The candidate grid printed by HoDoKu and Sudoku Explainer, one token of
+// candidates per cell, a single digit for a solved cell:
+//
+//   .----------------.----------------.----------------.
+//   | 5   3   124    | 26  7    2468  | 1489 19  248   |
+//   ...
+//   :----------------+----------------+----------------:
+//   ...
+//   '----------------'----------------'----------------'
+impl Sudoku {
+    // sets every Cell::grid from the text; a single digit is taken as a placed
+    // value and cleared from its peers, nothing else is recalculated
+    pub fn try_init_pencil_marks(&mut self, text: &str) -> Result<(), SudokuError> {
+        let size = self.size;
+        let tokens: Vec<&str> = text
+            .split(|ch: char| !ch.is_ascii_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tokens.len() != size * size {
+            return Err(SudokuError::InvalidLength(tokens.len(), size * size));
+        }
+        let mut grids = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            let mut grid = 0;
+            for ch in token.chars() {
+                match char_value(ch) {
+                    Some(v) if v as usize <= size => grid |= 1 << (v - 1),
+                    _ => return Err(SudokuError::InvalidDigit(index + 1, ch)),
+                }
+            }
+            grids.push(grid);
+        }
+        self.clean();
+        for (c, grid) in self.data.iter_mut().zip(grids) {
+            c.grid = grid;
+            if grid.count_ones() == 1 {
+                c.value = grid.trailing_zeros() as u8 + 1;
+                c.log.push(Note::Text(format!(
+                    "值 {} 由铅笔标记导入",
+                    value_char(c.value)
+                )));
+            } else {
+                c.log
+                    .push(Note::Text(String::from("可能值由铅笔标记导入.")));
+            }
+        }
+        self.apply_peers();
+        Ok(())
+    }
+    pub fn pencil_marks(&self) -> String {
+        let size = self.size;
+        let token = |r: usize, c: usize| -> String {
+            let cell = &self.data[[r, c]];
+            match cell.value {
+                0 => cell
+                    .get_values()
+                    .iter()
+                    .map(|&v| value_char(v as u8))
+                    .collect(),
+                v => value_char(v).to_string(),
+            }
+        };
+        let widths: Vec<usize> = (0..size)
+            .map(|c| (0..size).map(|r| token(r, c).len()).max().unwrap_or(1))
+            .collect();
+        let border = |left: char, middle: char, right: char| -> String {
+            let mut line = String::from(left);
+            for b in 0..size / self.box_columns {
+                if b > 0 {
+                    line.push(middle);
+                }
+                let columns = b * self.box_columns..(b + 1) * self.box_columns;
+                let width: usize = widths[columns].iter().map(|w| w + 2).sum();
+                line += &"-".repeat(width + 1);
+            }
+            line.push(right);
+            line
+        };
+        let mut text = border('.', '.', '.') + "\n";
+        for r in 0..size {
+            if r > 0 && r % self.box_rows == 0 {
+                text += &(border(':', '+', ':') + "\n");
+            }
+            let mut line = String::from("|");
+            for (c, &width) in widths.iter().enumerate() {
+                line += &format!(" {:<w$} ", token(r, c), w = width);
+                if (c + 1) % self.box_columns == 0 {
+                    line += " |";
+                }
+            }
+            text += &(line + "\n");
+        }
+        text + &border('\'', '\'', '\'') + "\n"
+    }
+}
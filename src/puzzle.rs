@@ -0,0 +1,364 @@
+use crate::{
+    char_value, value_char, EdgeKind, LineKind, OutsideKind, Parity, Rules, Side, Sudoku,
+    SudokuError,
+};
+
+// Text format for variant puzzles. Every line is either a directive or a
+// row of givens ('0' or '.' for blanks), '#' starts a comment:
+//
+//   size 2x3                   box rows x box columns, 3x3 when missing
+//   diagonal                   Sudoku-X
+//   windoku                    the four Windoku windows
+//   antiknight                 no equal digits a knight's move apart
+//   antiking                   no equal digits a king's move apart
+//   nonconsecutive             unmarked neighbours may not differ by 1
+//   miracle                    antiknight, antiking and nonconsecutive
+//   toroidal                   the rules above wrap around the edges
+//   kropkinegative             every consecutive or 1:2 pair has its dot
+//   white r1c1 r1c2            Kropki white dot between two neighbours
+//   black r1c1 r2c1            Kropki black dot
+//   xvnegative                 every pair adding up to 10 or 5 is marked
+//   x r1c1 r1c2                the pair adds up to 10
+//   v r1c1 r1c2                the pair adds up to 5
+//   less r1c1 r1c2             r1c1 < r1c2, "greater" for the other way round
+//   thermo r1c1 r1c2 r2c3      thermometer from the bulb
+//   arrow r1c1 r1c2 r1c3       the circle first, then the shaft
+//   sandwich row 3 15          sum between 1 and the largest digit, or column
+//   xsum top 10 . . 21 ...     X-sums along one side, '.' for no clue,
+//                              sides are top, bottom, left and right
+//   skyscraper left 3 . 2 ...  skyscrapers seen from one side
+//   littlekiller r1c2 se 15    diagonal sum from an edge cell, se/sw/ne/nw
+//   region r1c1 r1c2 ...       extra house
+//   disjoint                   disjoint groups, one house per place in a block
+//   group r1c2 r2c3 ...        no repeats, fewer cells than digits allowed
+//   argyle                     the eight Argyle diagonal lines as groups
+//   even r1c1 r5c5 ...         cells holding even digits, "odd" for odd ones
+//   jigsaw 111222333...        jigsaw blocks, one label per cell
+//   cage 15 r1c1 r1c2          killer cage with its sum
+//   ..3.2.6..
+//   9..3.5..1
+//   ...
+pub(crate) fn parse_cell(token: &str) -> Option<(usize, usize)> {
+    let token = token.to_ascii_lowercase();
+    let (r, c) = token.strip_prefix('r')?.split_once('c')?;
+    Some((r.parse().ok()?, c.parse().ok()?))
+}
+pub(crate) fn cell_name(pos: (usize, usize)) -> String {
+    format!("r{}c{}", pos.0, pos.1)
+}
+
+fn parse_cells(tokens: &[&str], line: usize) -> Result<Vec<(usize, usize)>, SudokuError> {
+    tokens
+        .iter()
+        .map(|t| parse_cell(t).ok_or(SudokuError::InvalidLine(line)))
+        .collect()
+}
+
+impl Sudoku {
+    pub fn parse_puzzle(text: &str) -> Result<Sudoku, SudokuError> {
+        let mut lines = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(n) => &line[..n],
+                None => line,
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if !tokens.is_empty() {
+                lines.push((i + 1, tokens));
+            }
+        }
+        let mut sudoku = Sudoku::new();
+        for (n, tokens) in &lines {
+            if tokens[0] == "size" {
+                let shape = tokens.get(1).and_then(|t| t.split_once('x'));
+                let (r, c) = match shape {
+                    Some((r, c)) => (r.parse(), c.parse()),
+                    None => return Err(SudokuError::InvalidLine(*n)),
+                };
+                match (r, c) {
+                    (Ok(r), Ok(c)) => sudoku = Sudoku::with_size(r, c)?,
+                    _ => return Err(SudokuError::InvalidLine(*n)),
+                }
+            }
+        }
+        let mut givens = String::new();
+        for (n, tokens) in lines {
+            let args = &tokens[1..];
+            match tokens[0] {
+                "size" => (),
+                "diagonal" => sudoku.set_diagonal(true),
+                "windoku" => sudoku.add_windoku_regions()?,
+                "antiknight" => sudoku.set_anti_knight(true),
+                "antiking" => sudoku.set_anti_king(true),
+                "nonconsecutive" => sudoku.set_non_consecutive(true),
+                "toroidal" => sudoku.set_toroidal(true),
+                "miracle" => sudoku.set_rules(Rules {
+                    toroidal: sudoku.is_toroidal(),
+                    ..Rules::miracle()
+                }),
+                "kropkinegative" => sudoku.set_kropki_negative(true),
+                "xvnegative" => sudoku.set_xv_negative(true),
+                "white" | "black" | "x" | "v" | "less" | "greater" => {
+                    let (a, b) = match parse_cells(args, n)?[..] {
+                        [a, b] => (a, b),
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                    match tokens[0] {
+                        "white" => sudoku.add_edge(a, b, EdgeKind::White)?,
+                        "black" => sudoku.add_edge(a, b, EdgeKind::Black)?,
+                        "x" => sudoku.add_edge(a, b, EdgeKind::X)?,
+                        "v" => sudoku.add_edge(a, b, EdgeKind::V)?,
+                        "less" => sudoku.add_edge(a, b, EdgeKind::Less)?,
+                        _ => sudoku.add_edge(b, a, EdgeKind::Less)?,
+                    };
+                }
+                "region" => {
+                    sudoku.add_region(parse_cells(args, n)?)?;
+                }
+                "disjoint" => sudoku.add_disjoint_groups()?,
+                "group" => {
+                    sudoku.add_group(parse_cells(args, n)?)?;
+                }
+                "argyle" => sudoku.add_argyle_groups()?,
+                "even" | "odd" => {
+                    let parity = match tokens[0] {
+                        "even" => Parity::Even,
+                        _ => Parity::Odd,
+                    };
+                    for pos in parse_cells(args, n)? {
+                        sudoku.set_parity(pos, parity)?;
+                    }
+                }
+                "jigsaw" => sudoku.set_jigsaw(&args.concat())?,
+                "cage" => {
+                    let sum = match args.first().map(|t| t.parse()) {
+                        Some(Ok(sum)) => sum,
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                    sudoku.add_cage(sum, parse_cells(&args[1..], n)?)?;
+                }
+                "thermo" => {
+                    sudoku.add_line(LineKind::Thermo, parse_cells(args, n)?)?;
+                }
+                "arrow" => {
+                    sudoku.add_line(LineKind::Arrow, parse_cells(args, n)?)?;
+                }
+                "sandwich" => {
+                    let (index, sum) = match args {
+                        [_, index, sum] => match (index.parse(), sum.parse()) {
+                            (Ok(index), Ok(sum)) => (index, sum),
+                            _ => return Err(SudokuError::InvalidLine(n)),
+                        },
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                    match args[0] {
+                        "row" => sudoku.add_sandwich_row(index, sum)?,
+                        "column" => sudoku.add_sandwich_column(index, sum)?,
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                }
+                "xsum" | "skyscraper" => {
+                    let side = match args.first() {
+                        Some(&"top") => Side::Top,
+                        Some(&"bottom") => Side::Bottom,
+                        Some(&"left") => Side::Left,
+                        Some(&"right") => Side::Right,
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                    for (i, token) in args[1..].iter().enumerate() {
+                        if *token == "." {
+                            continue;
+                        }
+                        let value = token.parse().map_err(|_| SudokuError::InvalidLine(n))?;
+                        match tokens[0] {
+                            "xsum" => sudoku.add_x_sum(side, i + 1, value)?,
+                            _ => sudoku.add_skyscraper(side, i + 1, value)?,
+                        };
+                    }
+                }
+                "littlekiller" => {
+                    let clue = match args {
+                        [cell, direction, sum] => {
+                            let step = match *direction {
+                                "se" => Some((1, 1)),
+                                "sw" => Some((1, -1)),
+                                "ne" => Some((-1, 1)),
+                                "nw" => Some((-1, -1)),
+                                _ => None,
+                            };
+                            (parse_cell(cell), step, sum.parse().ok())
+                        }
+                        _ => (None, None, None),
+                    };
+                    match clue {
+                        (Some(start), Some(step), Some(sum)) => {
+                            sudoku.add_little_killer(start, step, sum)?
+                        }
+                        _ => return Err(SudokuError::InvalidLine(n)),
+                    };
+                }
+                // anything else must be a row of givens, so a misspelled
+                // directive is reported with its line
+                _ => {
+                    let row = tokens.concat();
+                    let size = sudoku.size;
+                    let cell = |ch: char| {
+                        ch == '0' || ch == '.' || char_value(ch).is_some_and(|v| v as usize <= size)
+                    };
+                    if !row.chars().all(cell) {
+                        return Err(SudokuError::InvalidLine(n));
+                    }
+                    givens.push_str(&row);
+                }
+            }
+        }
+        if !givens.is_empty() {
+            sudoku.try_init(&givens)?;
+        }
+        Ok(sudoku)
+    }
+    pub fn to_puzzle_text(&self) -> String {
+        let mut text = String::new();
+        if (self.box_rows, self.box_columns) != (3, 3) {
+            text += &format!("size {}x{}\n", self.box_rows, self.box_columns);
+        }
+        if self.diagonal {
+            text += "diagonal\n";
+        }
+        if self.rules.anti_knight {
+            text += "antiknight\n";
+        }
+        if self.rules.anti_king {
+            text += "antiking\n";
+        }
+        if self.rules.non_consecutive {
+            text += "nonconsecutive\n";
+        }
+        if self.rules.toroidal {
+            text += "toroidal\n";
+        }
+        if self.kropki_negative {
+            text += "kropkinegative\n";
+        }
+        if self.xv_negative {
+            text += "xvnegative\n";
+        }
+        for region in &self.regions {
+            let cells: Vec<String> = region.iter().map(|&p| cell_name(p)).collect();
+            text += &format!("region {}\n", cells.join(" "));
+        }
+        for group in &self.groups {
+            let cells: Vec<String> = group.iter().map(|&p| cell_name(p)).collect();
+            text += &format!("group {}\n", cells.join(" "));
+        }
+        for (parity, name) in [(Parity::Even, "even"), (Parity::Odd, "odd")] {
+            let cells: Vec<String> = self
+                .parities
+                .iter()
+                .filter(|p| p.1 == parity)
+                .map(|p| cell_name(p.0))
+                .collect();
+            if !cells.is_empty() {
+                text += &format!("{} {}\n", name, cells.join(" "));
+            }
+        }
+        if let Some(map) = &self.jigsaw {
+            let labels: String = map.iter().map(|&b| value_char(b as u8)).collect();
+            text += &format!("jigsaw {}\n", labels);
+        }
+        for cage in &self.cages {
+            let cells: Vec<String> = cage.cells.iter().map(|&p| cell_name(p)).collect();
+            text += &format!("cage {} {}\n", cage.sum, cells.join(" "));
+        }
+        for edge in &self.edges {
+            let name = match edge.kind {
+                EdgeKind::White => "white",
+                EdgeKind::Black => "black",
+                EdgeKind::X => "x",
+                EdgeKind::V => "v",
+                EdgeKind::Less => "less",
+            };
+            text += &format!("{} {} {}\n", name, cell_name(edge.a), cell_name(edge.b));
+        }
+        for line in &self.lines {
+            let cells: Vec<String> = line.cells.iter().map(|&p| cell_name(p)).collect();
+            match line.kind {
+                LineKind::Thermo => text += &format!("thermo {}\n", cells.join(" ")),
+                LineKind::Arrow => text += &format!("arrow {}\n", cells.join(" ")),
+                LineKind::Sandwich(sum) => {
+                    let (a, b) = (line.cells[0], line.cells[1]);
+                    match a.0 == b.0 {
+                        true => text += &format!("sandwich row {} {}\n", a.0, sum),
+                        false => text += &format!("sandwich column {} {}\n", a.1, sum),
+                    }
+                }
+            }
+        }
+        for (kind, name) in [
+            (OutsideKind::XSum, "xsum"),
+            (OutsideKind::Skyscraper, "skyscraper"),
+        ] {
+            let sides = [
+                (Side::Top, "top"),
+                (Side::Bottom, "bottom"),
+                (Side::Left, "left"),
+                (Side::Right, "right"),
+            ];
+            for (side, side_name) in sides {
+                let mut values = vec![String::from("."); self.size];
+                let mut found = false;
+                for clue in self.outside.iter().filter(|c| c.kind == kind) {
+                    if let Some((s, index)) = self.clue_side(clue) {
+                        if s == side {
+                            values[index - 1] = clue.value.to_string();
+                            found = true;
+                        }
+                    }
+                }
+                if found {
+                    text += &format!("{} {} {}\n", name, side_name, values.join(" "));
+                }
+            }
+        }
+        for clue in &self.outside {
+            if clue.kind == OutsideKind::LittleKiller {
+                let direction = match clue.step {
+                    (1, 1) => "se",
+                    (1, _) => "sw",
+                    (_, 1) => "ne",
+                    _ => "nw",
+                };
+                let start = cell_name(clue.start);
+                text += &format!("littlekiller {} {} {}\n", start, direction, clue.value);
+            }
+        }
+        for r in 1..=self.size {
+            for c in self.get_row(r) {
+                text.push(match c.value {
+                    0 => '.',
+                    v => value_char(v),
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Sudoku, SudokuError};
+
+    #[test]
+    fn misspelled_directive_names_its_line() {
+        let text = "# Sudoku-X\ndiagonl\n".to_string()
+            + "530070000\n600195000\n098000060\n800060003\n400803001\n"
+            + "700020006\n060000280\n000419005\n000080079\n";
+        assert_eq!(
+            Sudoku::parse_puzzle(&text).err(),
+            Some(SudokuError::InvalidLine(2))
+        );
+        let fixed = text.replace("diagonl", "diagonal");
+        assert!(Sudoku::parse_puzzle(&fixed).unwrap().is_diagonal());
+    }
+}
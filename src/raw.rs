@@ -0,0 +1,218 @@
+use crate::{
+    cell_fits, value_char, Cage, Cell, Edge, Line, LineKind, OutsideClue, OutsideKind, Parity,
+    Rules, Snapshot, Sudoku, SudokuError,
+};
+
+// Saved boards are read into these plain copies first and rebuilt through
+// the same checks as add_cage, add_edge and the other setters, so an edited
+// file gives an error instead of a board that panics in calculate.
+#[derive(serde::Deserialize)]
+pub(crate) struct RawSudoku {
+    size: usize,
+    box_rows: usize,
+    box_columns: usize,
+    diagonal: bool,
+    regions: Vec<Vec<(usize, usize)>>,
+    groups: Vec<Vec<(usize, usize)>>,
+    jigsaw: Option<Vec<usize>>,
+    cages: Vec<Cage>,
+    edges: Vec<Edge>,
+    rules: Rules,
+    kropki_negative: bool,
+    xv_negative: bool,
+    lines: Vec<Line>,
+    parities: Vec<((usize, usize), Parity)>,
+    outside: Vec<OutsideClue>,
+    data: ndarray::Array2<Cell>,
+}
+
+impl TryFrom<RawSudoku> for Sudoku {
+    type Error = SudokuError;
+    fn try_from(raw: RawSudoku) -> Result<Sudoku, SudokuError> {
+        let mut sudoku = Sudoku::with_size(raw.box_rows, raw.box_columns)?;
+        let size = sudoku.size;
+        if raw.size != size {
+            return Err(SudokuError::InvalidSize(raw.box_rows, raw.box_columns));
+        }
+        let (rows, columns) = raw.data.dim();
+        if rows != size || columns != size {
+            let found = if rows != size { rows } else { columns };
+            return Err(SudokuError::InvalidLength(found, size));
+        }
+        for ((r, c), cell) in raw.data.indexed_iter() {
+            if (cell.row_id, cell.column_id, cell.size) != (r + 1, c + 1, size) {
+                return Err(SudokuError::InvalidPosition(cell.row_id, cell.column_id));
+            }
+            if !cell_fits(cell.value, cell.grid, size) || (cell.given && cell.value == 0) {
+                return Err(SudokuError::InvalidValue(cell.value));
+            }
+        }
+        // rules first, the neighbours of an edge depend on toroidal
+        sudoku.set_rules(raw.rules);
+        sudoku.set_diagonal(raw.diagonal);
+        if let Some(map) = raw.jigsaw {
+            let labels: String = map
+                .iter()
+                .map(|&b| u8::try_from(b).map_or('0', value_char))
+                .collect();
+            sudoku.set_jigsaw(&labels)?;
+        }
+        for cells in raw.regions {
+            sudoku.add_region(cells)?;
+        }
+        for cells in raw.groups {
+            sudoku.add_group(cells)?;
+        }
+        for cage in raw.cages {
+            sudoku.add_cage(cage.sum, cage.cells)?;
+        }
+        for edge in raw.edges {
+            sudoku.add_edge(edge.a, edge.b, edge.kind)?;
+        }
+        for line in raw.lines {
+            let LineKind::Sandwich(sum) = line.kind else {
+                sudoku.add_line(line.kind, line.cells)?;
+                continue;
+            };
+            let (r, c) = line.cells.first().copied().unwrap_or((0, 0));
+            let row: Vec<(usize, usize)> = (1..=size).map(|c| (r, c)).collect();
+            let column: Vec<(usize, usize)> = (1..=size).map(|r| (r, c)).collect();
+            if line.cells == row {
+                sudoku.add_sandwich_row(r, sum)?;
+            } else if line.cells == column {
+                sudoku.add_sandwich_column(c, sum)?;
+            } else {
+                return Err(SudokuError::InvalidPath((r, c)));
+            }
+        }
+        for (pos, parity) in raw.parities {
+            sudoku.set_parity(pos, parity)?;
+        }
+        for clue in raw.outside {
+            match (clue.kind, sudoku.clue_side(&clue)) {
+                (OutsideKind::LittleKiller, _) => {
+                    sudoku.add_little_killer(clue.start, clue.step, clue.value)?
+                }
+                (OutsideKind::XSum, Some((side, index))) => {
+                    sudoku.add_x_sum(side, index, clue.value)?
+                }
+                (OutsideKind::Skyscraper, Some((side, index))) => {
+                    sudoku.add_skyscraper(side, index, clue.value)?
+                }
+                _ => return Err(SudokuError::InvalidPath(clue.start)),
+            };
+        }
+        sudoku.set_kropki_negative(raw.kropki_negative);
+        sudoku.set_xv_negative(raw.xv_negative);
+        sudoku.data = raw.data;
+        // no digit may repeat among peers, as try_set_value ensures
+        for c in sudoku.data.iter().filter(|c| c.value != 0) {
+            let (pos, value) = ((c.row_id, c.column_id), c.value);
+            let peers = sudoku.peers(pos);
+            let with = peers
+                .into_iter()
+                .find(|&(r, c)| sudoku.data[[r - 1, c - 1]].value == value);
+            if let Some(with) = with {
+                return Err(SudokuError::Conflict { pos, value, with });
+            }
+        }
+        Ok(sudoku)
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct RawSnapshot {
+    cells: Vec<(u8, u32, bool, usize)>,
+}
+
+// the board size is not known here, try_restore checks against it
+impl TryFrom<RawSnapshot> for Snapshot {
+    type Error = SudokuError;
+    fn try_from(raw: RawSnapshot) -> Result<Snapshot, SudokuError> {
+        for &(value, grid, given, _) in &raw.cells {
+            if !cell_fits(value, grid, 25) || (given && value == 0) {
+                return Err(SudokuError::InvalidValue(value));
+            }
+        }
+        Ok(Snapshot { cells: raw.cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MultiSudoku, Snapshot, Sudoku, SudokuError};
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn load(json: &str) -> Result<Sudoku, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+    fn saved() -> String {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.add_cage(10, vec![(1, 3), (1, 4)]).unwrap();
+        sudoku.calculate();
+        serde_json::to_string(&sudoku).unwrap()
+    }
+
+    #[test]
+    fn saved_board_loads_back() {
+        let json = saved();
+        let sudoku = load(&json).unwrap();
+        assert_eq!(serde_json::to_string(&sudoku).unwrap(), json);
+    }
+
+    #[test]
+    fn wrong_size_is_rejected() {
+        let json = saved().replacen("\"size\":9", "\"size\":16", 1);
+        assert_eq!(
+            load(&json).err(),
+            Some(SudokuError::InvalidSize(3, 3).to_string())
+        );
+    }
+
+    #[test]
+    fn cage_outside_the_grid_is_rejected() {
+        let json = saved().replacen("[[1,3],[1,4]]", "[[1,3],[1,40]]", 1);
+        assert_eq!(
+            load(&json).err(),
+            Some(SudokuError::InvalidPosition(1, 40).to_string())
+        );
+    }
+
+    // r1c3 becomes a second 5 in row 1
+    #[test]
+    fn conflicting_board_is_rejected() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(&PUZZLE.replacen('0', "5", 1));
+        let json = serde_json::to_string(&sudoku).unwrap();
+        let conflict = SudokuError::Conflict {
+            pos: (1, 1),
+            value: 5,
+            with: (1, 3),
+        };
+        assert_eq!(load(&json).err(), Some(conflict.to_string()));
+    }
+
+    #[test]
+    fn snapshot_checked_against_the_board() {
+        let json = serde_json::to_string(&Sudoku::new().snapshot()).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        let mut small = Sudoku::with_size(2, 2).unwrap();
+        assert_eq!(
+            small.try_restore(&snapshot),
+            Err(SudokuError::InvalidLength(81, 16))
+        );
+        let bad = json.replacen("[0,511,", "[3,511,", 1);
+        assert!(serde_json::from_str::<Snapshot>(&bad).is_err());
+    }
+
+    #[test]
+    fn multi_layout_is_checked() {
+        let json = serde_json::to_string(&MultiSudoku::twodoku()).unwrap();
+        assert!(serde_json::from_str::<MultiSudoku>(&json).is_ok());
+        let bad = json.replacen("[6,6]", "[6,7]", 1);
+        assert!(serde_json::from_str::<MultiSudoku>(&bad).is_err());
+    }
+}
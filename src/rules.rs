@@ -0,0 +1,206 @@
+use crate::Sudoku;
+
+const KNIGHT_MOVES: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const KING_MOVES: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// global rules that hold between every pair of cells they relate, set once
+// per puzzle and combined freely
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rules {
+    pub anti_knight: bool,     // equal digits not a chess knight's move apart
+    pub anti_king: bool,       // equal digits not touching, diagonally included
+    pub non_consecutive: bool, // unmarked orthogonal neighbours not differing by 1
+    pub toroidal: bool,        // the grid wraps around, last row next to the first
+}
+impl Rules {
+    // anti-knight, anti-king and non-consecutive, as in the "Miracle Sudoku"
+    pub fn miracle() -> Rules {
+        Rules {
+            anti_knight: true,
+            anti_king: true,
+            non_consecutive: true,
+            toroidal: false,
+        }
+    }
+}
+
+impl Sudoku {
+    pub fn set_rules(&mut self, rules: Rules) {
+        // turning a rule off must give back what it eliminated
+        self.rules = rules;
+        self.recompute_grids();
+    }
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+    pub fn set_anti_knight(&mut self, anti_knight: bool) {
+        self.set_rules(Rules {
+            anti_knight,
+            ..self.rules
+        });
+    }
+    pub fn is_anti_knight(&self) -> bool {
+        self.rules.anti_knight
+    }
+    pub fn set_anti_king(&mut self, anti_king: bool) {
+        self.set_rules(Rules {
+            anti_king,
+            ..self.rules
+        });
+    }
+    pub fn is_anti_king(&self) -> bool {
+        self.rules.anti_king
+    }
+    pub fn set_non_consecutive(&mut self, non_consecutive: bool) {
+        self.set_rules(Rules {
+            non_consecutive,
+            ..self.rules
+        });
+    }
+    pub fn is_non_consecutive(&self) -> bool {
+        self.rules.non_consecutive
+    }
+    pub fn set_toroidal(&mut self, toroidal: bool) {
+        self.set_rules(Rules {
+            toroidal,
+            ..self.rules
+        });
+    }
+    pub fn is_toroidal(&self) -> bool {
+        self.rules.toroidal
+    }
+    // the cell reached by a step, wrapping around when the grid is toroidal
+    fn step(&self, pos: (usize, usize), (dr, dc): (isize, isize)) -> Option<(usize, usize)> {
+        let size = self.size as isize;
+        let (mut r, mut c) = (pos.0 as isize - 1 + dr, pos.1 as isize - 1 + dc);
+        if self.rules.toroidal {
+            r = r.rem_euclid(size);
+            c = c.rem_euclid(size);
+        }
+        match (0..size).contains(&r) && (0..size).contains(&c) {
+            true => Some((r as usize + 1, c as usize + 1)),
+            false => None,
+        }
+    }
+    // cells that see pos through the chess rules
+    pub fn chess_peers(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
+        if self.rules.anti_knight {
+            moves.extend(KNIGHT_MOVES);
+        }
+        if self.rules.anti_king {
+            moves.extend(KING_MOVES);
+        }
+        let mut result = Vec::new();
+        for m in moves {
+            if let Some(p) = self.step(pos, m) {
+                if p != pos && !result.contains(&p) {
+                    result.push(p);
+                }
+            }
+        }
+        result
+    }
+    // orthogonal neighbours, across the edges too on a toroidal grid
+    pub fn neighbours(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        for m in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            if let Some(p) = self.step(pos, m) {
+                if p != pos && !result.contains(&p) {
+                    result.push(p);
+                }
+            }
+        }
+        result
+    }
+    // every cell that may not repeat the digit of pos: its row, column and
+    // block, the extra houses and groups containing it, and the chess rules
+    pub fn peers(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut result: Vec<(usize, usize)> = Vec::new();
+        let mut add = |p: (usize, usize)| {
+            if p != pos && !result.contains(&p) {
+                result.push(p);
+            }
+        };
+        for c in self.get_row(pos.0) {
+            add((c.row_id, c.column_id));
+        }
+        for c in self.get_column(pos.1) {
+            add((c.row_id, c.column_id));
+        }
+        for c in self.get_block(self.get_block_index(pos)) {
+            add((c.row_id, c.column_id));
+        }
+        for house in self.peer_groups() {
+            if self.house_contains(house, pos) {
+                for c in self.get_house(house) {
+                    add((c.row_id, c.column_id));
+                }
+            }
+        }
+        for p in self.chess_peers(pos) {
+            add(p);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Rules, Sudoku};
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    // a rule turned on and off again leaves the classic puzzle solvable
+    #[test]
+    fn rules_turned_off_give_back_candidates() {
+        let toggles: [fn(&mut Sudoku, bool); 3] = [
+            Sudoku::set_anti_knight,
+            Sudoku::set_anti_king,
+            Sudoku::set_non_consecutive,
+        ];
+        for toggle in toggles {
+            let mut sudoku = Sudoku::new();
+            sudoku.init(PUZZLE);
+            toggle(&mut sudoku, true);
+            toggle(&mut sudoku, false);
+            assert_eq!(sudoku.count_solutions(2), 1);
+            assert!(sudoku.search());
+        }
+    }
+
+    #[test]
+    fn toroidal_turned_off_gives_back_candidates() {
+        let mut sudoku = Sudoku::new();
+        sudoku.init(PUZZLE);
+        sudoku.set_rules(Rules {
+            anti_king: true,
+            toroidal: true,
+            ..Rules::default()
+        });
+        sudoku.set_toroidal(false);
+        sudoku.set_anti_king(false);
+        assert_eq!(sudoku.count_solutions(2), 1);
+        assert!(sudoku.search());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{value_char, Step, Sudoku};
+use std::fmt::Write;
+
+const CELL: f64 = 48.0; // pixels per cell
+const MARGIN: f64 = 4.0;
+
+// row, column and digit
+pub type Candidate = (usize, usize, u8);
+
+// what a hint points at: cells to shade, candidates it removes and the links
+// of a chain between candidates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Highlight {
+    pub cells: Vec<(usize, usize)>,
+    pub eliminated: Vec<Candidate>,
+    pub links: Vec<(Candidate, Candidate)>,
+}
+
+impl Highlight {
+    // the cells placed and the candidates removed by some trace steps
+    pub fn from_steps(steps: &[Step]) -> Highlight {
+        let mut highlight = Highlight::default();
+        for step in steps {
+            if step.value != 0 && !highlight.cells.contains(&step.pos) {
+                highlight.cells.push(step.pos);
+            }
+            for &v in &step.removed {
+                highlight.eliminated.push((step.pos.0, step.pos.1, v));
+            }
+        }
+        highlight
+    }
+}
+
+impl Sudoku {
+    // an SVG image of the board: givens in black, solved values in blue and
+    // the candidates of empty cells; eliminated candidates of the highlight
+    // are drawn in red and crossed out
+    pub fn to_svg(&self, highlight: Option<&Highlight>) -> String {
+        let empty = Highlight::default();
+        let highlight = highlight.unwrap_or(&empty);
+        let side = CELL * self.size as f64 + 2.0 * MARGIN;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\" font-family=\"sans-serif\">",
+            side
+        );
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{0}\" height=\"{0}\" fill=\"#ffffff\"/>",
+            side
+        );
+        for &(r, c) in &highlight.cells {
+            if self.try_get_cell((r, c)).is_err() {
+                continue;
+            }
+            let (x, y) = corner((r, c));
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{}\" height=\"{}\" fill=\"#fff2a8\"/>",
+                x, y, CELL, CELL
+            );
+        }
+        self.svg_lines(&mut svg);
+        for &(a, b) in &highlight.links {
+            if !self.is_candidate(a) || !self.is_candidate(b) {
+                continue;
+            }
+            let (x1, y1) = self.candidate_centre(a);
+            let (x2, y2) = self.candidate_centre(b);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#2a9d3a\" stroke-width=\"2\"/>",
+                x1, y1, x2, y2
+            );
+            for (x, y) in [(x1, y1), (x2, y2)] {
+                let _ = writeln!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"#d8f3dc\" stroke=\"#2a9d3a\"/>",
+                    x,
+                    y,
+                    self.candidate_font() * 0.7
+                );
+            }
+        }
+        for cell in &self.data {
+            let pos = (cell.row_id, cell.column_id);
+            if cell.value != 0 {
+                let (x, y) = corner(pos);
+                let (fill, weight) = match cell.given {
+                    true => ("#000000", "bold"),
+                    false => ("#1f5fbf", "normal"),
+                };
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" font-weight=\"{}\" fill=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                    x + CELL / 2.0,
+                    y + CELL / 2.0,
+                    CELL * 0.65,
+                    weight,
+                    fill,
+                    value_char(cell.value)
+                );
+                continue;
+            }
+            for v in 1..=self.size as u8 {
+                let eliminated = highlight.eliminated.contains(&(pos.0, pos.1, v));
+                if !eliminated && !cell.check_bit(v as usize - 1) {
+                    continue;
+                }
+                let (x, y) = self.candidate_centre((pos.0, pos.1, v));
+                let font = self.candidate_font();
+                let fill = match eliminated {
+                    true => "#d62828",
+                    false => "#555555",
+                };
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" fill=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                    x,
+                    y,
+                    font,
+                    fill,
+                    value_char(v)
+                );
+                if eliminated {
+                    let d = font * 0.45;
+                    let _ = writeln!(
+                        svg,
+                        "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#d62828\" stroke-width=\"1.5\"/>",
+                        x - d,
+                        y + d,
+                        x + d,
+                        y - d
+                    );
+                }
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+    // thin lines between cells, thick ones between blocks and around the grid
+    fn svg_lines(&self, svg: &mut String) {
+        let end = MARGIN + CELL * self.size as f64;
+        for i in 1..self.size {
+            let at = MARGIN + CELL * i as f64;
+            let _ = writeln!(
+                svg,
+                "<path d=\"M{0:.1} {1:.1}H{2:.1}M{1:.1} {0:.1}V{2:.1}\" stroke=\"#999999\" stroke-width=\"1\"/>",
+                MARGIN, at, end
+            );
+        }
+        let mut thick = String::new();
+        for (pos, right) in self.block_sides() {
+            let (x, y) = corner(pos);
+            let _ = match right {
+                true => write!(thick, "M{:.1} {:.1}V{:.1}", x + CELL, y, y + CELL),
+                false => write!(thick, "M{:.1} {:.1}H{:.1}", x, y + CELL, x + CELL),
+            };
+        }
+        let _ = writeln!(
+            svg,
+            "<path d=\"{}\" stroke=\"#000000\" stroke-width=\"3\" stroke-linecap=\"square\"/>",
+            thick
+        );
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{0:.1}\" y=\"{0:.1}\" width=\"{1:.1}\" height=\"{1:.1}\" fill=\"none\" stroke=\"#000000\" stroke-width=\"3\"/>",
+            MARGIN,
+            end - MARGIN
+        );
+    }
+    // candidates sit in a box_rows x box_columns mini-grid, 1 at the top left
+    fn candidate_centre(&self, candidate: Candidate) -> (f64, f64) {
+        let (x, y) = corner((candidate.0, candidate.1));
+        let i = candidate.2 as usize - 1;
+        let w = CELL / self.box_columns as f64;
+        let h = CELL / self.box_rows as f64;
+        (
+            x + w * ((i % self.box_columns) as f64 + 0.5),
+            y + h * ((i / self.box_columns) as f64 + 0.5),
+        )
+    }
+    fn is_candidate(&self, candidate: Candidate) -> bool {
+        self.try_get_cell((candidate.0, candidate.1)).is_ok()
+            && (1..=self.size).contains(&(candidate.2 as usize))
+    }
+    fn candidate_font(&self) -> f64 {
+        CELL / self.box_rows.max(self.box_columns) as f64 * 0.7
+    }
+}
+
+fn corner(pos: (usize, usize)) -> (f64, f64) {
+    (
+        MARGIN + CELL * (pos.1 - 1) as f64,
+        MARGIN + CELL * (pos.0 - 1) as f64,
+    )
+}
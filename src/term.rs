@@ -0,0 +1,136 @@
+use crate::{value_char, Cell, Highlight, Sudoku};
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+
+// box drawing characters by their arms, index up * 27 + right * 9 + down * 3
+// + left, each arm 0 for none, 1 for light and 2 for heavy
+const JUNCTIONS: &str =
+    " ╴╸╷┐┑╻┒┓╶─╾┌┬┭┎┰┱╺╼━┍┮┯┏┲┳╵┘┙│┤┥╽┧┪└┴┵├┼┽┟╁╅┕┶┷┝┾┿┢╆╈╹┚┛╿┦┩┃┨┫┖┸┹┞╀╃┠╂╉┗┺┻┡╄╇┣╊╋";
+
+const GIVEN: &str = "1";
+const SOLVED: &str = "34";
+const CANDIDATE: &str = "90";
+const ELIMINATED: &str = "31;9";
+const HIGHLIGHT: &str = "43";
+
+fn junction(arms: [usize; 4]) -> char {
+    let index = arms[0] * 27 + arms[1] * 9 + arms[2] * 3 + arms[3];
+    JUNCTIONS.chars().nth(index).unwrap_or(' ')
+}
+
+// text in ANSI colors, codes such as "1" or "31;9"
+fn paint(text: &str, codes: &[&str], color: bool) -> String {
+    match color && !codes.is_empty() {
+        true => format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text),
+        false => text.to_string(),
+    }
+}
+
+impl Sudoku {
+    // the board for a terminal: heavy lines around blocks, every empty cell
+    // with its candidates in a mini-grid; with color givens are bold, solved
+    // values blue, and the cells and eliminations of the highlight marked
+    pub fn to_terminal(&self, highlight: Option<&Highlight>, color: bool) -> String {
+        let empty = Highlight::default();
+        let highlight = highlight.unwrap_or(&empty);
+        let n = self.size;
+        let width = 2 * self.box_columns + 1;
+        let mut right = HashSet::new();
+        let mut bottom = HashSet::new();
+        for (pos, side) in self.block_sides() {
+            match side {
+                true => right.insert(pos),
+                false => bottom.insert(pos),
+            };
+        }
+        // weight of the border right of or below cell (r, c), 0 being the edge
+        let vertical = |r: usize, c: usize| match c == 0 || c == n || right.contains(&(r, c)) {
+            true => 2,
+            false => 1,
+        };
+        let horizontal = |r: usize, c: usize| match r == 0 || r == n || bottom.contains(&(r, c)) {
+            true => 2,
+            false => 1,
+        };
+        let mut text = String::new();
+        for r in 0..=n {
+            for c in 0..=n {
+                let arms = [
+                    if r > 0 { vertical(r, c) } else { 0 },
+                    if c < n { horizontal(r, c + 1) } else { 0 },
+                    if r < n { vertical(r + 1, c) } else { 0 },
+                    if c > 0 { horizontal(r, c) } else { 0 },
+                ];
+                text.push(junction(arms));
+                if c < n {
+                    let line = junction([0, arms[1], 0, arms[1]]);
+                    text.extend(std::iter::repeat_n(line, width));
+                }
+            }
+            text.push('\n');
+            if r == n {
+                break;
+            }
+            for k in 0..self.box_rows {
+                for c in 0..=n {
+                    text.push(junction([vertical(r + 1, c), 0, vertical(r + 1, c), 0]));
+                    if c < n {
+                        text += &self.terminal_cell(&self.data[[r, c]], k, highlight, color);
+                    }
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+    // line k of a cell, a value in the middle or a row of candidates
+    fn terminal_cell(&self, cell: &Cell, k: usize, highlight: &Highlight, color: bool) -> String {
+        let pos = (cell.row_id, cell.column_id);
+        let mut base = Vec::new();
+        if highlight.cells.contains(&pos) {
+            base.push(HIGHLIGHT);
+        }
+        let mut text = String::new();
+        if cell.value != 0 {
+            let width = 2 * self.box_columns + 1;
+            if k != self.box_rows / 2 {
+                return paint(&" ".repeat(width), &base, color);
+            }
+            let mut codes = base.clone();
+            codes.push(match cell.given {
+                true => GIVEN,
+                false => SOLVED,
+            });
+            text += &paint(&" ".repeat(width / 2), &base, color);
+            text += &paint(&value_char(cell.value).to_string(), &codes, color);
+            text += &paint(&" ".repeat(width / 2), &base, color);
+            return text;
+        }
+        for i in 0..self.box_columns {
+            let v = (k * self.box_columns + i + 1) as u8;
+            text += &paint(" ", &base, color);
+            let mut codes = base.clone();
+            let digit = match v as usize <= self.size && cell.check_bit(v as usize - 1) {
+                true => {
+                    codes.push(CANDIDATE);
+                    value_char(v)
+                }
+                false if color && highlight.eliminated.contains(&(pos.0, pos.1, v)) => {
+                    codes.push(ELIMINATED);
+                    value_char(v)
+                }
+                false => ' ',
+            };
+            text += &paint(&digit.to_string(), &codes, color);
+        }
+        text += &paint(" ", &base, color);
+        text
+    }
+    // prints with colors when stdout is a terminal
+    pub fn print_highlight(&self, highlight: &Highlight) {
+        print!(
+            "{}",
+            self.to_terminal(Some(highlight), io::stdout().is_terminal())
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::Sudoku;
+
+type Technique = (&'static str, fn(&mut Sudoku));
+
+// the deductions calculate runs, in order, by name
+pub(crate) const TECHNIQUES: [Technique; 12] = [
+    ("single", Sudoku::check_all_cells),
+    ("row", Sudoku::check_row),
+    ("column", Sudoku::check_column),
+    ("block", Sudoku::check_block),
+    ("extra_houses", Sudoku::check_extra_houses),
+    ("pairs", Sudoku::check_2and2),
+    ("row_column_in_block", Sudoku::check_row_column_in_block),
+    ("cages", Sudoku::check_cages),
+    ("innies_outies", Sudoku::check_innies_outies),
+    ("edges", Sudoku::check_edges),
+    ("lines", Sudoku::check_lines),
+    ("outside_clues", Sudoku::check_outside_clues),
+];
+
+// what one technique changed in one cell; value is 0 when only candidates
+// were removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub technique: String,
+    pub pos: (usize, usize),
+    pub value: u8,
+    pub removed: Vec<u8>,
+}
+
+impl Sudoku {
+    // calculate, recording each technique's placements and eliminations
+    pub fn calculate_steps(&mut self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        loop {
+            let count = self.remaining_count();
+            for (name, technique) in TECHNIQUES {
+                let before = self.snapshot();
+                technique(self);
+                steps.extend(self.diff_steps(&before, name));
+            }
+            if count == self.remaining_count() {
+                break;
+            }
+        }
+        steps
+    }
+    // logic first, then whatever the search had to guess
+    pub fn solve_steps(&mut self) -> Vec<Step> {
+        let mut steps = self.calculate_steps();
+        if self.remaining_count().0 != 0 {
+            let before = self.snapshot();
+            if self.search() {
+                steps.extend(self.diff_steps(&before, "search"));
+            }
+        }
+        steps
+    }
+    fn diff_steps(&self, before: &crate::Snapshot, technique: &str) -> Vec<Step> {
+        let mut steps = Vec::new();
+        for (cell, &(value, grid, _, _)) in self.data.iter().zip(&before.cells) {
+            let removed = grid & !cell.grid;
+            if cell.value == value && removed == 0 {
+                continue;
+            }
+            steps.push(Step {
+                technique: technique.to_string(),
+                pos: (cell.row_id, cell.column_id),
+                value: match cell.value == value {
+                    true => 0,
+                    false => cell.value,
+                },
+                removed: (0..self.size as u8)
+                    .filter(|&bit| removed & (1 << bit) != 0)
+                    .map(|bit| bit + 1)
+                    .collect(),
+            });
+        }
+        steps
+    }
+}